@@ -0,0 +1,603 @@
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::value::{self, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::FromIterator;
+use std::slice;
+use std::vec;
+
+/// Key of a [`PhpArray`], an integer or a byte string like in PHP.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ArrayKey {
+    Int(i64),
+    String(Vec<u8>),
+}
+
+impl fmt::Display for ArrayKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArrayKey::Int(n) => write!(f, "{}", n),
+            ArrayKey::String(s) => f.write_str(&String::from_utf8_lossy(s)),
+        }
+    }
+}
+
+impl ArrayKey {
+    /// Turn a string holding a decimal integer in canonical form, like `"42"`
+    /// but not `"042"`, `"+1"` or `"-0"`, into an integer key like PHP does.
+    fn normalize(self) -> Self {
+        match self {
+            ArrayKey::String(s) if is_canonical_int(&s) => {
+                match std::str::from_utf8(&s).unwrap().parse() {
+                    Ok(n) => ArrayKey::Int(n),
+                    Err(_) => ArrayKey::String(s),
+                }
+            }
+            key => key,
+        }
+    }
+}
+
+fn is_canonical_int(s: &[u8]) -> bool {
+    let digits = s.strip_prefix(b"-").unwrap_or(s);
+    match digits {
+        [b'0'] => s.len() == 1,
+        [b'1'..=b'9', rest @ ..] => rest.iter().all(u8::is_ascii_digit),
+        _ => false,
+    }
+}
+
+impl From<i64> for ArrayKey {
+    fn from(n: i64) -> Self {
+        ArrayKey::Int(n)
+    }
+}
+
+impl From<i32> for ArrayKey {
+    fn from(n: i32) -> Self {
+        ArrayKey::Int(n.into())
+    }
+}
+
+impl From<u32> for ArrayKey {
+    fn from(n: u32) -> Self {
+        ArrayKey::Int(n.into())
+    }
+}
+
+impl From<usize> for ArrayKey {
+    fn from(n: usize) -> Self {
+        ArrayKey::Int(n as i64)
+    }
+}
+
+impl From<&str> for ArrayKey {
+    fn from(s: &str) -> Self {
+        ArrayKey::from(s.as_bytes())
+    }
+}
+
+impl From<String> for ArrayKey {
+    fn from(s: String) -> Self {
+        ArrayKey::from(s.into_bytes())
+    }
+}
+
+impl From<&String> for ArrayKey {
+    fn from(s: &String) -> Self {
+        ArrayKey::from(s.as_str())
+    }
+}
+
+impl From<&[u8]> for ArrayKey {
+    fn from(s: &[u8]) -> Self {
+        ArrayKey::String(s.to_vec()).normalize()
+    }
+}
+
+impl From<Vec<u8>> for ArrayKey {
+    fn from(s: Vec<u8>) -> Self {
+        ArrayKey::String(s).normalize()
+    }
+}
+
+impl From<&ArrayKey> for ArrayKey {
+    fn from(key: &ArrayKey) -> Self {
+        key.clone()
+    }
+}
+
+impl Serialize for ArrayKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            ArrayKey::Int(n) => serializer.serialize_i64(*n),
+            ArrayKey::String(s) => match std::str::from_utf8(s) {
+                Ok(s) => serializer.serialize_str(s),
+                Err(_) => {
+                    serializer.serialize_newtype_struct(value::BYTES_TOKEN, &value::byte_string(s))
+                }
+            },
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ArrayKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct KeyVisitor;
+
+        impl<'de> Visitor<'de> for KeyVisitor {
+            type Value = ArrayKey;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an integer or a string")
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<ArrayKey, E> {
+                Ok(ArrayKey::Int(v))
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<ArrayKey, E>
+            where
+                E: de::Error,
+            {
+                if v > i64::MAX as u64 {
+                    return Err(de::Error::invalid_value(de::Unexpected::Unsigned(v), &self));
+                }
+                Ok(ArrayKey::Int(v as i64))
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<ArrayKey, E> {
+                Ok(ArrayKey::from(v))
+            }
+
+            fn visit_string<E>(self, v: String) -> Result<ArrayKey, E> {
+                Ok(ArrayKey::from(v))
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<ArrayKey, E> {
+                Ok(ArrayKey::from(v))
+            }
+
+            fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<ArrayKey, E> {
+                Ok(ArrayKey::from(v))
+            }
+        }
+
+        deserializer.deserialize_any(KeyVisitor)
+    }
+}
+
+/// Index at which PHP appends to an array, shared by [`PhpArray`] and the
+/// parser for the entries without key.
+///
+/// It's one past the largest integer key ever inserted, or 0 before any. Like
+/// in PHP 8.3, negative keys count too, so the index after `-5` is `-4`, where
+/// older versions used 0.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct NextIndex(Option<i64>);
+
+impl NextIndex {
+    pub(crate) fn insert(&mut self, n: i64) {
+        if self.0.is_none_or(|next| n >= next) {
+            self.0 = Some(n.saturating_add(1));
+        }
+    }
+
+    /// Index to append at, `None` once `i64::MAX` is used, where PHP fails to
+    /// append.
+    pub(crate) fn get(self, is_used: impl FnOnce(i64) -> bool) -> Option<i64> {
+        match self.0 {
+            None => Some(0),
+            Some(i64::MAX) if is_used(i64::MAX) => None,
+            Some(next) => Some(next),
+        }
+    }
+}
+
+/// Ordered map of [`ArrayKey`] to [`Value`], like the arrays of PHP.
+///
+/// Keys are normalized like in PHP, so `"1"` and `1` are the same key while
+/// `"01"` stays a string, and [`push`](PhpArray::push) appends at the next
+/// free index, following PHP 8.3 for negative keys like
+/// [`from_str`](crate::from_str) does.
+///
+/// ```rust
+/// use serde_var_export::{PhpArray, Value};
+///
+/// let mut array = PhpArray::new();
+/// array.insert("5", Value::Int(1));
+/// array.insert("name", Value::Int(2));
+/// assert_eq!(array.push(Value::Int(3)), Some(6));
+/// assert_eq!(array.get(5), Some(&Value::Int(1)));
+/// assert_eq!(
+///     serde_var_export::to_string(&array).unwrap(),
+///     "array(
+///   5 => 1,
+///   'name' => 2,
+///   6 => 3,
+/// )"
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PhpArray {
+    entries: Vec<(ArrayKey, Value)>,
+    positions: HashMap<ArrayKey, usize>,
+    next_index: NextIndex,
+}
+
+impl PhpArray {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get<K>(&self, key: K) -> Option<&Value>
+    where
+        K: Into<ArrayKey>,
+    {
+        let &i = self.positions.get(&key.into().normalize())?;
+        Some(&self.entries[i].1)
+    }
+
+    pub fn get_mut<K>(&mut self, key: K) -> Option<&mut Value>
+    where
+        K: Into<ArrayKey>,
+    {
+        let &i = self.positions.get(&key.into().normalize())?;
+        Some(&mut self.entries[i].1)
+    }
+
+    pub fn contains_key<K>(&self, key: K) -> bool
+    where
+        K: Into<ArrayKey>,
+    {
+        self.positions.contains_key(&key.into().normalize())
+    }
+
+    /// Set the value of `key`, in place if the key already exists and at the
+    /// end otherwise, and return the previous value.
+    pub fn insert<K>(&mut self, key: K, value: Value) -> Option<Value>
+    where
+        K: Into<ArrayKey>,
+    {
+        let key = key.into().normalize();
+        if let ArrayKey::Int(n) = key {
+            self.next_index.insert(n);
+        }
+        match self.positions.get(&key) {
+            Some(&i) => Some(std::mem::replace(&mut self.entries[i].1, value)),
+            None => {
+                self.positions.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Index [`push`](PhpArray::push) would append at: one past the largest
+    /// integer key ever inserted, even if it was removed since or negative, or
+    /// 0.
+    ///
+    /// `None` once `i64::MAX` is used, where PHP fails to append.
+    pub fn next_index(&self) -> Option<i64> {
+        self.next_index
+            .get(|n| self.positions.contains_key(&ArrayKey::Int(n)))
+    }
+
+    /// Append `value` like `$array[] = $value` and return its index, or
+    /// `None` without appending if there is no next index.
+    pub fn push(&mut self, value: Value) -> Option<i64> {
+        let index = self.next_index()?;
+        self.insert(index, value);
+        Some(index)
+    }
+
+    /// Remove `key` and return its value, keeping the order of the other
+    /// entries.
+    pub fn remove<K>(&mut self, key: K) -> Option<Value>
+    where
+        K: Into<ArrayKey>,
+    {
+        let i = self.positions.remove(&key.into().normalize())?;
+        let (_, value) = self.entries.remove(i);
+        for (key, _) in &self.entries[i..] {
+            *self.positions.get_mut(key).unwrap() -= 1;
+        }
+        Some(value)
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            iter: self.entries.iter(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&ArrayKey, &mut Value)> {
+        self.entries.iter_mut().map(|(key, value)| (&*key, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &ArrayKey> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    /// Whether the keys are 0, 1, 2... in order, like `array_is_list()`.
+    pub fn is_list(&self) -> bool {
+        self.keys()
+            .enumerate()
+            .all(|(i, key)| *key == ArrayKey::Int(i as i64))
+    }
+}
+
+/// Arrays are equal if they have the same entries in the same order, like
+/// PHP's `===`.
+impl PartialEq for PhpArray {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+pub struct Iter<'a> {
+    iter: slice::Iter<'a, (ArrayKey, Value)>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a ArrayKey, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(key, value)| (key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a> IntoIterator for &'a PhpArray {
+    type Item = (&'a ArrayKey, &'a Value);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl IntoIterator for PhpArray {
+    type Item = (ArrayKey, Value);
+    type IntoIter = vec::IntoIter<(ArrayKey, Value)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<K> FromIterator<(K, Value)> for PhpArray
+where
+    K: Into<ArrayKey>,
+{
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (K, Value)>,
+    {
+        let mut array = PhpArray::new();
+        for (key, value) in iter {
+            array.insert(key, value);
+        }
+        array
+    }
+}
+
+/// Values are appended like with [`push`](PhpArray::push).
+impl FromIterator<Value> for PhpArray {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = Value>,
+    {
+        let mut array = PhpArray::new();
+        for value in iter {
+            array.push(value);
+        }
+        array
+    }
+}
+
+impl Serialize for PhpArray {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for PhpArray {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(ArrayVisitor)
+    }
+}
+
+pub(crate) struct ArrayVisitor;
+
+impl ArrayVisitor {
+    /// Collect the remaining entries of `map` into `array`.
+    pub(crate) fn visit_entries<'de, A>(
+        mut array: PhpArray,
+        mut map: A,
+    ) -> Result<PhpArray, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        while let Some((key, value)) = map.next_entry::<ArrayKey, Value>()? {
+            array.insert(key, value);
+        }
+        Ok(array)
+    }
+}
+
+impl<'de> Visitor<'de> for ArrayVisitor {
+    type Value = PhpArray;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array")
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<PhpArray, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        ArrayVisitor::visit_entries(PhpArray::new(), map)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<PhpArray, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut array = PhpArray::new();
+        while let Some(value) = seq.next_element()? {
+            array.push(value);
+        }
+        Ok(array)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_keys() {
+        assert_eq!(ArrayKey::from("42"), ArrayKey::Int(42));
+        assert_eq!(ArrayKey::from("-7"), ArrayKey::Int(-7));
+        assert_eq!(ArrayKey::from("0"), ArrayKey::Int(0));
+        for s in &[
+            "",
+            "-0",
+            "042",
+            "+1",
+            "1.5",
+            " 1",
+            "1e3",
+            "9223372036854775808",
+        ] {
+            assert_eq!(ArrayKey::from(*s), ArrayKey::String(s.as_bytes().to_vec()));
+        }
+        assert_eq!(
+            ArrayKey::from("-9223372036854775808"),
+            ArrayKey::Int(i64::MIN)
+        );
+
+        let mut array = PhpArray::new();
+        array.insert("1", Value::Null);
+        array.insert(1, Value::Bool(true));
+        assert_eq!(array.len(), 1);
+        assert_eq!(array.get("1"), Some(&Value::Bool(true)));
+        assert_eq!(
+            array.get(ArrayKey::String(b"1".to_vec())),
+            Some(&Value::Bool(true))
+        );
+    }
+
+    #[test]
+    fn push() {
+        let mut array = PhpArray::new();
+        assert_eq!(array.push(Value::Null), Some(0));
+        array.insert("a", Value::Null);
+        array.insert(10, Value::Null);
+        array.insert(3, Value::Null);
+        assert_eq!(array.push(Value::Null), Some(11));
+        array.remove(11);
+        assert_eq!(array.push(Value::Null), Some(12));
+
+        let mut array = PhpArray::new();
+        array.insert(-5, Value::Null);
+        assert_eq!(array.push(Value::Null), Some(-4));
+        let parsed: Value = crate::from_str("array(-5 => NULL, NULL)").unwrap();
+        assert_eq!(parsed, Value::Array(array));
+
+        let mut array = PhpArray::new();
+        array.insert(i64::MAX, Value::Null);
+        assert_eq!(array.push(Value::Null), None);
+        assert_eq!(array.len(), 1);
+    }
+
+    #[test]
+    fn remove() {
+        let mut array: PhpArray = vec![
+            ("a", Value::Int(1)),
+            ("b", Value::Int(2)),
+            ("c", Value::Int(3)),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(array.remove("b"), Some(Value::Int(2)));
+        assert_eq!(array.remove("b"), None);
+        assert_eq!(array.get("c"), Some(&Value::Int(3)));
+        let keys: Vec<_> = array.keys().cloned().collect();
+        assert_eq!(keys, vec![ArrayKey::from("a"), ArrayKey::from("c")]);
+    }
+
+    #[test]
+    fn is_list() {
+        let mut array: PhpArray = vec![Value::Int(1), Value::Int(2)].into_iter().collect();
+        assert!(array.is_list());
+        array.remove(0);
+        assert!(!array.is_list());
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let array: PhpArray = crate::from_str(
+            "array(
+  '1' => 'a',
+  '01' => 'b',
+  -3 => 'c',
+  'key' => array('x', 'y'),
+)",
+        )
+        .unwrap();
+        assert_eq!(array.get(1), Some(&Value::String(b"a".to_vec())));
+        assert_eq!(array.get("01"), Some(&Value::String(b"b".to_vec())));
+        assert_eq!(
+            crate::to_string(&array).unwrap(),
+            "array(
+  1 => 'a',
+  '01' => 'b',
+  -3 => 'c',
+  'key' =>
+  array(
+    0 => 'x',
+    1 => 'y',
+  ),
+)"
+        );
+
+        let list: PhpArray = crate::from_str("array('a', 5 => 'b')").unwrap();
+        let keys: Vec<_> = list.keys().cloned().collect();
+        assert_eq!(keys, vec![ArrayKey::Int(0), ArrayKey::Int(5)]);
+    }
+}
@@ -0,0 +1,1008 @@
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Visitor};
+use serde::{forward_to_deserialize_any, Deserialize};
+
+use crate::error::{Error, Result};
+use crate::lexer::error_at;
+use crate::parse::{Expr, ExprKind, Key, Parser, STD_CLASS};
+use crate::value::{self, Value};
+use std::borrow::Cow;
+use std::io::Read;
+use std::path::Path;
+use std::vec;
+
+pub fn from_str<'de, T>(s: &'de str) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    from_slice(s.as_bytes())
+}
+
+pub fn from_slice<'de, T>(v: &'de [u8]) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    let mut deserializer = Deserializer::from_slice(v);
+    let value = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+pub fn from_reader<R, T>(mut reader: R) -> Result<T>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+    from_slice(&buf)
+}
+
+/// Deserialize the value returned by a PHP file like `<?php return [...];`.
+///
+/// The `declare`, `namespace` and `use` statements before the `return` are
+/// skipped, any other statement is an error.
+///
+/// ```rust
+/// let config: std::collections::HashMap<String, u16> = serde_var_export::from_php_file_str(
+///     "<?php
+///
+/// declare(strict_types=1);
+///
+/// return [
+///     'port' => 8080,
+/// ];
+/// ",
+/// )
+/// .unwrap();
+/// assert_eq!(config["port"], 8080);
+/// ```
+pub fn from_php_file_str<'de, T>(s: &'de str) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    from_php_file_slice(s.as_bytes())
+}
+
+/// Read the PHP file at `path` and deserialize the value it returns, like
+/// [`from_php_file_str`].
+pub fn from_php_file<P, T>(path: P) -> Result<T>
+where
+    P: AsRef<Path>,
+    T: DeserializeOwned,
+{
+    let buf = std::fs::read(path)?;
+    from_php_file_slice(&buf)
+}
+
+fn from_php_file_slice<'de, T>(v: &'de [u8]) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    let mut deserializer = Deserializer::from_slice(v);
+    deserializer.parser.begin_file()?;
+    let value = T::deserialize(&mut deserializer)?;
+    deserializer.parser.end_file()?;
+    Ok(value)
+}
+
+/// Deserializer of PHP values in the format written by `var_export()`.
+pub struct Deserializer<'de> {
+    parser: Parser<'de>,
+    /// Value given to [`from_value`](Deserializer::from_value) instead of an
+    /// input, until it's deserialized.
+    value: Option<Expr<'de>>,
+    options: Options,
+}
+
+/// Options of a [`Deserializer`] which apply to all the values.
+#[derive(Debug, Clone, Copy, Default)]
+struct Options {
+    strict_list_order: bool,
+    fill_list_gaps: bool,
+    arrays_as_maps: bool,
+}
+
+impl<'de> Deserializer<'de> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &'de str) -> Self {
+        Self::from_slice(s.as_bytes())
+    }
+
+    pub fn from_slice(v: &'de [u8]) -> Self {
+        Self {
+            parser: Parser::new(v),
+            value: None,
+            options: Options::default(),
+        }
+    }
+
+    /// Deserialize `value` like the input it could be written as, with the
+    /// same options.
+    ///
+    /// ```rust
+    /// use serde::Deserialize;
+    /// use serde_var_export::{php, Deserializer};
+    ///
+    /// let value = php!([1 => "b", 0 => "a", 3 => "d"]);
+    /// let mut deserializer = Deserializer::from_value(&value);
+    /// deserializer.fill_list_gaps(true);
+    /// let list = Vec::<String>::deserialize(&mut deserializer).unwrap();
+    /// assert_eq!(list, ["a", "b", "", "d"]);
+    /// ```
+    pub fn from_value(value: &'de Value) -> Self {
+        Self {
+            parser: Parser::new(b""),
+            value: Some(value.as_expr()),
+            options: Options::default(),
+        }
+    }
+
+    /// Require the keys of the arrays deserialized as sequences to be in
+    /// order, like `array(0 => 'a', 1 => 'b')`. By default they can be in any
+    /// order, like `array(1 => 'b', 0 => 'a')`, and the values are sorted by
+    /// key.
+    pub fn strict_list_order(&mut self, strict: bool) -> &mut Self {
+        self.options.strict_list_order = strict;
+        self
+    }
+
+    /// Make self-describing types like untagged enums see all the arrays as
+    /// maps. By default the arrays which are lists according to PHP's
+    /// `array_is_list()` are seen as sequences.
+    ///
+    /// These types see integer keys as integers, so a variant of an untagged
+    /// enum with a map keyed by `String` doesn't match `array(1 => 2)`, unlike
+    /// the same map outside of the enum. Its key type has to accept integers,
+    /// like `i64` or an untagged enum of `i64` and `String`.
+    pub fn arrays_as_maps(&mut self, as_maps: bool) -> &mut Self {
+        self.options.arrays_as_maps = as_maps;
+        self
+    }
+
+    /// Accept arrays with missing keys as sequences, like the ones left by
+    /// `unset()`, filling the gaps up to the largest key with default values:
+    /// `0`, `false`, `''`, an empty sequence or map, or `None`. The gaps can
+    /// hold at most as many values as the array has entries, plus 1024.
+    ///
+    /// By default such arrays are an error.
+    pub fn fill_list_gaps(&mut self, fill: bool) -> &mut Self {
+        self.options.fill_list_gaps = fill;
+        self
+    }
+
+    /// Fail unless only whitespace is left in the input.
+    pub fn end(&mut self) -> Result<()> {
+        self.parser.end()
+    }
+}
+
+/// State shared by the deserializers of the values of a [`Deserializer`].
+#[derive(Clone, Copy, Default)]
+struct Context<'de> {
+    /// Whole input, to locate the errors, unless the values come from a
+    /// [`Value`].
+    input: Option<&'de [u8]>,
+    options: Options,
+}
+
+impl<'de> Context<'de> {
+    /// Function attaching the position `offset` to an error.
+    fn error_at(self, offset: usize) -> impl FnOnce(Error) -> Error + 'de {
+        move |err| match self.input {
+            Some(input) => error_at(input, offset, err),
+            None => err,
+        }
+    }
+}
+
+macro_rules! forward_to_expr {
+    ($($method:ident($($arg:ident: $ty:ty),*))*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                let (expr, input) = match self.value.take() {
+                    Some(expr) => (expr, None),
+                    None => (self.parser.parse_value()?, Some(self.parser.input())),
+                };
+                let cx = Context {
+                    input,
+                    options: self.options,
+                };
+                let value = ExprDeserializer::new(expr, cx);
+                let at = value.error_at();
+                value.$method($($arg,)* visitor).map_err(at)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    forward_to_expr! {
+        deserialize_any()
+        deserialize_bool()
+        deserialize_i8()
+        deserialize_i16()
+        deserialize_i32()
+        deserialize_i64()
+        deserialize_u8()
+        deserialize_u16()
+        deserialize_u32()
+        deserialize_u64()
+        deserialize_f32()
+        deserialize_f64()
+        deserialize_char()
+        deserialize_str()
+        deserialize_string()
+        deserialize_bytes()
+        deserialize_byte_buf()
+        deserialize_option()
+        deserialize_unit()
+        deserialize_unit_struct(name: &'static str)
+        deserialize_newtype_struct(name: &'static str)
+        deserialize_seq()
+        deserialize_tuple(len: usize)
+        deserialize_tuple_struct(name: &'static str, len: usize)
+        deserialize_map()
+        deserialize_struct(name: &'static str, fields: &'static [&'static str])
+        deserialize_enum(name: &'static str, variants: &'static [&'static str])
+        deserialize_identifier()
+        deserialize_ignored_any()
+    }
+}
+
+/// Deserializer of an already parsed value.
+struct ExprDeserializer<'de> {
+    expr: Expr<'de>,
+    cx: Context<'de>,
+}
+
+impl<'de> ExprDeserializer<'de> {
+    fn new(expr: Expr<'de>, cx: Context<'de>) -> Self {
+        Self { expr, cx }
+    }
+
+    /// Function attaching the position of the value to the errors of its
+    /// deserialization.
+    fn error_at(&self) -> impl FnOnce(Error) -> Error + 'de {
+        self.cx.error_at(self.expr.offset)
+    }
+
+    /// Turn a `stdClass` object into the array of its properties, as it has no
+    /// class which could be matched.
+    fn std_class_as_array(self) -> Self {
+        let kind = match self.expr.kind {
+            ExprKind::Object {
+                class: STD_CLASS,
+                properties,
+            } => ExprKind::Array(properties),
+            kind => kind,
+        };
+        Self::new(
+            Expr {
+                kind,
+                offset: self.expr.offset,
+            },
+            self.cx,
+        )
+    }
+}
+
+fn visit_str<'de, V>(s: Cow<'de, [u8]>, visitor: V) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    match s {
+        Cow::Borrowed(b) => match std::str::from_utf8(b) {
+            Ok(s) => visitor.visit_borrowed_str(s),
+            Err(_) => visitor.visit_borrowed_bytes(b),
+        },
+        Cow::Owned(v) => match String::from_utf8(v) {
+            Ok(s) => visitor.visit_string(s),
+            Err(err) => visitor.visit_byte_buf(err.into_bytes()),
+        },
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ExprDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.expr.kind {
+            ExprKind::Null => visitor.visit_unit(),
+            ExprKind::Bool(b) => visitor.visit_bool(b),
+            ExprKind::Int(n) => visitor.visit_i64(n),
+            ExprKind::Float(n) => visitor.visit_f64(n),
+            ExprKind::Str(s) => visit_str(s, visitor),
+            ExprKind::Array(entries) if !self.cx.options.arrays_as_maps && is_list(&entries) => {
+                let values = entries.into_iter().map(|(_, value)| Some(value)).collect();
+                visitor.visit_seq(SeqDeserializer::new(values, self.cx))
+            }
+            ExprKind::Array(entries) => visitor.visit_map(MapDeserializer::new(entries, self.cx)),
+            ExprKind::Object { properties, .. } => {
+                visitor.visit_map(MapDeserializer::new(properties, self.cx))
+            }
+            ExprKind::EnumCase { case, .. } => visitor.visit_borrowed_str(case),
+            ExprKind::Raw(expr) => Err(de::Error::custom(format_args!(
+                "raw PHP `{}` is not a value",
+                expr
+            ))),
+        }
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.expr.kind {
+            ExprKind::Array(entries)
+            | ExprKind::Object {
+                properties: entries,
+                ..
+            } => visitor.visit_map(MapDeserializer::new(entries, self.cx)),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.expr.kind {
+            ExprKind::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if name != value::TOKEN {
+            return visitor.visit_newtype_struct(self);
+        }
+        // Give a `Value` what the other types don't see: the keys of lists,
+        // the class of objects and the enum cases.
+        let offset = self.expr.offset;
+        let str_expr = |s: Cow<'de, [u8]>| Expr {
+            kind: ExprKind::Str(s),
+            offset,
+        };
+        let entries = match self.expr.kind {
+            ExprKind::Array(entries) => entries,
+            ExprKind::Object { class, properties } => {
+                let class_key = Key::Str(Cow::Borrowed(value::CLASS_KEY.as_bytes()));
+                let class = str_expr(Cow::Borrowed(class.as_bytes()));
+                std::iter::once((class_key, class))
+                    .chain(properties)
+                    .collect()
+            }
+            ExprKind::EnumCase { class, case } => {
+                let case_key = Key::Str(Cow::Borrowed(value::ENUM_CASE_KEY.as_bytes()));
+                let case = format!("{}::{}", class, case).into_bytes();
+                vec![(case_key, str_expr(Cow::Owned(case)))]
+            }
+            ExprKind::Raw(expr) => {
+                let raw_key = Key::Str(Cow::Borrowed(value::RAW_KEY.as_bytes()));
+                vec![(raw_key, str_expr(Cow::Borrowed(expr.as_bytes())))]
+            }
+            _ => return self.deserialize_any(visitor),
+        };
+        visitor.visit_map(MapDeserializer::new(entries, self.cx))
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let this = self.std_class_as_array();
+        match this.expr.kind {
+            ExprKind::Array(entries) => {
+                let values = list_values(entries, this.cx.options)?;
+                visitor.visit_seq(SeqDeserializer::new(values, this.cx))
+            }
+            _ => this.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.expr.kind {
+            ExprKind::Str(Cow::Borrowed(b)) => visitor.visit_borrowed_bytes(b),
+            ExprKind::Str(Cow::Owned(v)) => visitor.visit_byte_buf(v),
+            _ => self.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    /// A string or an enum case is a unit variant and an array with a single
+    /// entry is a variant with a value. An object is the variant named like its
+    /// class, with or without the namespace, and its properties as value.
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let this = self.std_class_as_array();
+        match this.expr.kind {
+            ExprKind::Object { class, properties } => {
+                let short = class.rsplit('\\').next().unwrap_or(class);
+                let variant = [class, short]
+                    .iter()
+                    .find_map(|name| variants.iter().find(|variant| *variant == name))
+                    .ok_or_else(|| Error::UnknownClass(class.to_owned()))?;
+                visitor.visit_enum(EnumDeserializer {
+                    variant: Key::Str(Cow::Borrowed(variant.as_bytes())),
+                    value: Some(ExprDeserializer::new(
+                        Expr {
+                            kind: ExprKind::Array(properties),
+                            offset: this.expr.offset,
+                        },
+                        this.cx,
+                    )),
+                })
+            }
+            ExprKind::Str(s) => visitor.visit_enum(EnumDeserializer {
+                variant: Key::Str(s),
+                value: None,
+            }),
+            ExprKind::EnumCase { case, .. } => visitor.visit_enum(EnumDeserializer {
+                variant: Key::Str(Cow::Borrowed(case.as_bytes())),
+                value: None,
+            }),
+            ExprKind::Array(entries) if entries.len() == 1 => {
+                let (variant, value) = entries.into_iter().next().unwrap();
+                visitor.visit_enum(EnumDeserializer {
+                    variant,
+                    value: Some(ExprDeserializer::new(value, this.cx)),
+                })
+            }
+            _ => Err(de::Error::invalid_type(
+                this.unexpected(),
+                &"string or array with a single entry",
+            )),
+        }
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct identifier
+    }
+}
+
+impl ExprDeserializer<'_> {
+    fn unexpected(&self) -> de::Unexpected<'_> {
+        match &self.expr.kind {
+            ExprKind::Null => de::Unexpected::Unit,
+            ExprKind::Bool(b) => de::Unexpected::Bool(*b),
+            ExprKind::Int(n) => de::Unexpected::Signed(*n),
+            ExprKind::Float(n) => de::Unexpected::Float(*n),
+            ExprKind::Str(_) => de::Unexpected::Other("string"),
+            ExprKind::Array(_) => de::Unexpected::Map,
+            ExprKind::Object { .. } => de::Unexpected::Other("object"),
+            ExprKind::EnumCase { .. } => de::Unexpected::Other("enum case"),
+            ExprKind::Raw(_) => de::Unexpected::Other("raw PHP"),
+        }
+    }
+}
+
+/// Whether the keys of the array are `0`, `1`, ... in order, like with PHP's
+/// `array_is_list()`.
+fn is_list(entries: &[(Key, Expr)]) -> bool {
+    entries
+        .iter()
+        .enumerate()
+        .all(|(i, (key, _))| *key == Key::Int(i as i64))
+}
+
+/// Missing keys filled in a list beyond one per entry of the array, so that a
+/// large key can't make it allocate without bounds.
+const MAX_LIST_GAPS: u64 = 1024;
+
+/// Values of an array with list keys, sorted by key, with `None` for the gaps.
+fn list_values<'de>(
+    entries: Vec<(Key<'de>, Expr<'de>)>,
+    options: Options,
+) -> Result<Vec<Option<Expr<'de>>>> {
+    if is_list(&entries) {
+        return Ok(entries.into_iter().map(|(_, value)| Some(value)).collect());
+    }
+
+    let mut indexed = Vec::with_capacity(entries.len());
+    for (key, value) in entries {
+        match key {
+            Key::Int(n) if n >= 0 => indexed.push((n as u64, value)),
+            key => {
+                return Err(de::Error::custom(format_args!(
+                    "expected a list, found key {}",
+                    key
+                )))
+            }
+        }
+    }
+    if options.strict_list_order {
+        let mut expected = 0;
+        for (n, _) in &indexed {
+            if *n < expected || (*n > expected && !options.fill_list_gaps) {
+                return Err(de::Error::custom(format_args!(
+                    "expected key {} in list, found key {}",
+                    expected, n
+                )));
+            }
+            expected = n + 1;
+        }
+    } else {
+        indexed.sort_by_key(|(n, _)| *n);
+    }
+    if let Some(&(largest, _)) = indexed.last() {
+        let gaps = largest + 1 - indexed.len() as u64;
+        if options.fill_list_gaps && gaps > indexed.len() as u64 + MAX_LIST_GAPS {
+            return Err(de::Error::custom(format_args!(
+                "too many missing keys in list, up to key {}",
+                largest
+            )));
+        }
+    }
+
+    let mut values = Vec::with_capacity(indexed.len());
+    for (n, value) in indexed {
+        if n > values.len() as u64 {
+            if !options.fill_list_gaps {
+                return Err(de::Error::custom(format_args!(
+                    "expected a list, key {} is missing",
+                    values.len()
+                )));
+            }
+            values.resize_with(n as usize, || None);
+        }
+        values.push(Some(value));
+    }
+    Ok(values)
+}
+
+struct SeqDeserializer<'de> {
+    iter: vec::IntoIter<Option<Expr<'de>>>,
+    cx: Context<'de>,
+}
+
+impl<'de> SeqDeserializer<'de> {
+    fn new(values: Vec<Option<Expr<'de>>>, cx: Context<'de>) -> Self {
+        Self {
+            iter: values.into_iter(),
+            cx,
+        }
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for SeqDeserializer<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some(Some(value)) => {
+                let value = ExprDeserializer::new(value, self.cx);
+                let at = value.error_at();
+                seed.deserialize(value).map(Some).map_err(at)
+            }
+            Some(None) => seed.deserialize(DefaultDeserializer).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct MapDeserializer<'de> {
+    iter: vec::IntoIter<(Key<'de>, Expr<'de>)>,
+    value: Option<Expr<'de>>,
+    cx: Context<'de>,
+}
+
+impl<'de> MapDeserializer<'de> {
+    fn new(entries: Vec<(Key<'de>, Expr<'de>)>, cx: Context<'de>) -> Self {
+        Self {
+            iter: entries.into_iter(),
+            value: None,
+            cx,
+        }
+    }
+}
+
+impl<'de> de::MapAccess<'de> for MapDeserializer<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((key, value)) => {
+                // Keys have no position of their own, the one of the value is
+                // close enough.
+                let at = self.cx.error_at(value.offset);
+                self.value = Some(value);
+                seed.deserialize(KeyDeserializer { key })
+                    .map(Some)
+                    .map_err(at)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(value) => {
+                let value = ExprDeserializer::new(value, self.cx);
+                let at = value.error_at();
+                seed.deserialize(value).map_err(at)
+            }
+            None => Err(de::Error::custom("value is missing")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Deserializer of an array key, which also accepts integer keys as strings.
+struct KeyDeserializer<'de> {
+    key: Key<'de>,
+}
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.key {
+            Key::Int(n) => visitor.visit_i64(n),
+            Key::Str(s) => visit_str(s, visitor),
+        }
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.key {
+            Key::Int(n) => visitor.visit_string(n.to_string()),
+            Key::Str(s) => visit_str(s, visitor),
+        }
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(EnumDeserializer {
+            variant: self.key,
+            value: None,
+        })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char bytes byte_buf
+        unit unit_struct seq tuple tuple_struct map struct ignored_any
+    }
+}
+
+struct EnumDeserializer<'de> {
+    variant: Key<'de>,
+    value: Option<ExprDeserializer<'de>>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer<'de> {
+    type Error = Error;
+    type Variant = VariantDeserializer<'de>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(KeyDeserializer { key: self.variant })?;
+        Ok((variant, VariantDeserializer { value: self.value }))
+    }
+}
+
+struct VariantDeserializer<'de> {
+    value: Option<ExprDeserializer<'de>>,
+}
+
+impl<'de> de::VariantAccess<'de> for VariantDeserializer<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        match self.value {
+            // Object without properties.
+            Some(ExprDeserializer {
+                expr:
+                    Expr {
+                        kind: ExprKind::Array(entries),
+                        ..
+                    },
+                ..
+            }) if entries.is_empty() => Ok(()),
+            Some(value) => {
+                let at = value.error_at();
+                de::Deserialize::deserialize(value).map_err(at)
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.value {
+            Some(value) => {
+                let at = value.error_at();
+                seed.deserialize(value).map_err(at)
+            }
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Some(value) => {
+                let at = value.error_at();
+                de::Deserializer::deserialize_seq(value, visitor).map_err(at)
+            }
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Some(value) => {
+                let at = value.error_at();
+                de::Deserializer::deserialize_map(value, visitor).map_err(at)
+            }
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
+
+macro_rules! forward_to_default {
+    ($($method:ident => $target:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                self.$target(visitor)
+            }
+        )*
+    };
+}
+
+/// Deserializer of the default value of the requested type, for the gaps of
+/// lists.
+struct DefaultDeserializer;
+
+impl<'de> de::Deserializer<'de> for DefaultDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_bool(false)
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i64(0)
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u64(0)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_f64(0.0)
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_str("")
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_bytes(b"")
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_none()
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(SeqDeserializer::new(Vec::new(), Context::default()))
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(MapDeserializer::new(Vec::new(), Context::default()))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        char unit unit_struct tuple tuple_struct enum identifier ignored_any
+    }
+
+    forward_to_default! {
+        deserialize_i8 => deserialize_i64
+        deserialize_i16 => deserialize_i64
+        deserialize_i32 => deserialize_i64
+        deserialize_i128 => deserialize_i64
+        deserialize_u8 => deserialize_u64
+        deserialize_u16 => deserialize_u64
+        deserialize_u32 => deserialize_u64
+        deserialize_u128 => deserialize_u64
+        deserialize_f32 => deserialize_f64
+        deserialize_string => deserialize_str
+        deserialize_byte_buf => deserialize_bytes
+    }
+}
@@ -11,6 +11,52 @@ pub enum Error {
     #[error("{0}")]
     Message(String),
 
+    #[error("array key must be an integer or a string")]
+    InvalidKey,
+
+    #[error("integer {0} overflows PHP int")]
+    IntegerOverflow(String),
+
+    #[error("{message} at line {line} column {column}")]
+    Syntax {
+        message: String,
+        line: usize,
+        column: usize,
+        /// Byte offset in the input.
+        offset: usize,
+        /// Line of the input with a caret under the error, see
+        /// [`Error::snippet`].
+        snippet: String,
+    },
+
+    #[error("unknown class {0}")]
+    UnknownClass(String),
+
+    #[error("at {path}: {source}")]
+    AtPath {
+        path: String,
+        #[source]
+        source: Box<Error>,
+    },
+
+    /// Error of the deserialized value at a position of the input, like a type
+    /// mismatch.
+    #[error("{source} at line {line} column {column}")]
+    AtPosition {
+        line: usize,
+        column: usize,
+        offset: usize,
+        snippet: String,
+        #[source]
+        source: Box<Error>,
+    },
+
+    #[error("depth limit of {limit} exceeded at {path}")]
+    DepthLimitExceeded { limit: usize, path: String },
+
+    #[error("size limit of {limit} bytes exceeded")]
+    SizeLimitExceeded { limit: usize },
+
     #[error("io error: {0:?}")]
     Io(#[from] io::Error),
 
@@ -18,7 +64,110 @@ pub enum Error {
     FromUtf8(#[from] FromUtf8Error),
 }
 
+/// Classification of an [`Error`], see [`Error::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Custom error of a `Serialize` or `Deserialize` implementation.
+    Custom,
+    InvalidKey,
+    IntegerOverflow,
+    Syntax,
+    UnknownClass,
+    DepthLimitExceeded,
+    SizeLimitExceeded,
+    Io,
+    FromUtf8,
+}
+
 impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Message(_) => ErrorKind::Custom,
+            Error::InvalidKey => ErrorKind::InvalidKey,
+            Error::IntegerOverflow(_) => ErrorKind::IntegerOverflow,
+            Error::Syntax { .. } => ErrorKind::Syntax,
+            Error::UnknownClass(_) => ErrorKind::UnknownClass,
+            Error::AtPath { source, .. } | Error::AtPosition { source, .. } => source.kind(),
+            Error::DepthLimitExceeded { .. } => ErrorKind::DepthLimitExceeded,
+            Error::SizeLimitExceeded { .. } => ErrorKind::SizeLimitExceeded,
+            Error::Io(_) => ErrorKind::Io,
+            Error::FromUtf8(_) => ErrorKind::FromUtf8,
+        }
+    }
+
+    pub fn is_io(&self) -> bool {
+        self.kind() == ErrorKind::Io
+    }
+
+    pub fn is_syntax(&self) -> bool {
+        self.kind() == ErrorKind::Syntax
+    }
+
+    /// Whether the error is caused by a value which can't be represented,
+    /// rather than by the input syntax, a limit or the I/O.
+    pub fn is_data(&self) -> bool {
+        matches!(
+            self.kind(),
+            ErrorKind::Custom
+                | ErrorKind::InvalidKey
+                | ErrorKind::IntegerOverflow
+                | ErrorKind::UnknownClass
+        )
+    }
+
+    /// One-based line of the input where a deserialization error occurred.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Error::Syntax { line, .. } | Error::AtPosition { line, .. } => Some(*line),
+            Error::AtPath { source, .. } => source.line(),
+            _ => None,
+        }
+    }
+
+    /// One-based column of the input where a deserialization error occurred,
+    /// counted in characters.
+    pub fn column(&self) -> Option<usize> {
+        match self {
+            Error::Syntax { column, .. } | Error::AtPosition { column, .. } => Some(*column),
+            Error::AtPath { source, .. } => source.column(),
+            _ => None,
+        }
+    }
+
+    /// Byte offset of the input where a deserialization error occurred.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Error::Syntax { offset, .. } | Error::AtPosition { offset, .. } => Some(*offset),
+            Error::AtPath { source, .. } => source.offset(),
+            _ => None,
+        }
+    }
+
+    /// Line of the input where a deserialization error occurred, with a caret
+    /// under the error:
+    ///
+    /// ```text
+    ///   3 |   'port' => '80',
+    ///     |             ^
+    /// ```
+    pub fn snippet(&self) -> Option<&str> {
+        match self {
+            Error::Syntax { snippet, .. } | Error::AtPosition { snippet, .. } => Some(snippet),
+            Error::AtPath { source, .. } => source.snippet(),
+            _ => None,
+        }
+    }
+
+    /// Path of the value at which the error occurred, like
+    /// `database.connections[2].port`.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Error::AtPath { path, .. } | Error::DepthLimitExceeded { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
     #[cfg(test)]
     fn to_message(&self) -> Option<&str> {
         if let Error::Message(msg) = &self {
@@ -43,7 +192,7 @@ impl de::Error for Error {
 
 #[cfg(test)]
 mod tests {
-    use super::Error;
+    use super::{Error, ErrorKind};
     use serde::{de, ser};
 
     #[test]
@@ -52,6 +201,33 @@ mod tests {
         assert_eq!(err.to_message(), Some("some message"));
     }
 
+    #[test]
+    fn kind() {
+        let err: Error = ser::Error::custom("some message");
+        assert_eq!(err.kind(), ErrorKind::Custom);
+        assert!(err.is_data());
+
+        let err = Error::AtPath {
+            path: "foo".to_owned(),
+            source: Box::new(Error::Syntax {
+                message: "unexpected `)`".to_owned(),
+                line: 2,
+                column: 3,
+                offset: 10,
+                snippet: String::new(),
+            }),
+        };
+        assert_eq!(err.kind(), ErrorKind::Syntax);
+        assert!(err.is_syntax());
+        assert_eq!((err.line(), err.column()), (Some(2), Some(3)));
+        assert_eq!(err.offset(), Some(10));
+
+        let err = Error::from(std::io::Error::from(std::io::ErrorKind::Other));
+        assert!(err.is_io());
+        assert!(!err.is_data());
+        assert_eq!(err.line(), None);
+    }
+
     #[test]
     fn de_error() {
         let err: Error = de::Error::custom("some message");
@@ -0,0 +1,751 @@
+use crate::error::{Error, Result};
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Token<'de> {
+    Int(i64),
+    Float(f64),
+    Str(Cow<'de, [u8]>),
+    /// Identifier or qualified name like `array`, `NULL` or `\Foo\Bar`.
+    Name(&'de str),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    Comma,
+    Semicolon,
+    Equals,
+    Arrow,
+    Minus,
+    Plus,
+    Dot,
+    DoubleColon,
+    /// `?>`
+    CloseTag,
+    Eof,
+}
+
+impl Token<'_> {
+    pub(crate) fn describe(&self) -> String {
+        match self {
+            Token::Int(_) => "integer".to_owned(),
+            Token::Float(_) => "float".to_owned(),
+            Token::Str(_) => "string".to_owned(),
+            Token::Name(name) => format!("`{}`", name),
+            Token::LParen => "`(`".to_owned(),
+            Token::RParen => "`)`".to_owned(),
+            Token::LBracket => "`[`".to_owned(),
+            Token::RBracket => "`]`".to_owned(),
+            Token::LBrace => "`{`".to_owned(),
+            Token::RBrace => "`}`".to_owned(),
+            Token::Comma => "`,`".to_owned(),
+            Token::Semicolon => "`;`".to_owned(),
+            Token::Equals => "`=`".to_owned(),
+            Token::Arrow => "`=>`".to_owned(),
+            Token::Minus => "`-`".to_owned(),
+            Token::Plus => "`+`".to_owned(),
+            Token::Dot => "`.`".to_owned(),
+            Token::DoubleColon => "`::`".to_owned(),
+            Token::CloseTag => "`?>`".to_owned(),
+            Token::Eof => "end of input".to_owned(),
+        }
+    }
+}
+
+pub(crate) struct Lexer<'de> {
+    input: &'de [u8],
+    pos: usize,
+}
+
+impl<'de> Lexer<'de> {
+    pub(crate) fn new(input: &'de [u8]) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    /// Read the next token and the offset where it starts.
+    pub(crate) fn next_token(&mut self) -> Result<(Token<'de>, usize)> {
+        self.skip_trivia()?;
+        let start = self.pos;
+        let b = match self.input.get(self.pos) {
+            Some(&b) => b,
+            None => return Ok((Token::Eof, start)),
+        };
+        let token = match b {
+            b'(' => self.punct(1, Token::LParen),
+            b')' => self.punct(1, Token::RParen),
+            b'[' => self.punct(1, Token::LBracket),
+            b']' => self.punct(1, Token::RBracket),
+            b'{' => self.punct(1, Token::LBrace),
+            b'}' => self.punct(1, Token::RBrace),
+            b',' => self.punct(1, Token::Comma),
+            b';' => self.punct(1, Token::Semicolon),
+            b'-' => self.punct(1, Token::Minus),
+            b'+' => self.punct(1, Token::Plus),
+            b'.' if !self.peek_at(1).is_some_and(|b| b.is_ascii_digit()) => {
+                self.punct(1, Token::Dot)
+            }
+            b'=' if self.peek_at(1) == Some(b'>') => self.punct(2, Token::Arrow),
+            b'=' if self.peek_at(1) != Some(b'=') => self.punct(1, Token::Equals),
+            b'?' if self.peek_at(1) == Some(b'>') => self.punct(2, Token::CloseTag),
+            b':' if self.peek_at(1) == Some(b':') => self.punct(2, Token::DoubleColon),
+            b'<' if self.input[self.pos..].starts_with(b"<<<") => self.heredoc()?,
+            b'\'' => self.single_quoted()?,
+            b'"' => self.double_quoted()?,
+            b'0'..=b'9' | b'.' => self.number()?,
+            b'\\' => self.name(),
+            b if is_name_start(b) => self.name(),
+            _ => {
+                return Err(self.error(start, "unexpected character"));
+            }
+        };
+        Ok((token, start))
+    }
+
+    pub(crate) fn input(&self) -> &'de [u8] {
+        self.input
+    }
+
+    /// Skip the `<?php` open tag at the start of a PHP file, after an optional
+    /// byte order mark or shebang line.
+    pub(crate) fn open_tag(&mut self) -> Result<()> {
+        if self.input.starts_with(b"\xEF\xBB\xBF") {
+            self.pos += 3;
+        }
+        if self.input[self.pos..].starts_with(b"#!") {
+            self.skip_line();
+            if self.peek_at(0) == Some(b'\n') {
+                self.pos += 1;
+            }
+        }
+        let tag = self.input.get(self.pos..self.pos + 5);
+        if !tag.is_some_and(|tag| tag.eq_ignore_ascii_case(b"<?php"))
+            || self.peek_at(5).is_some_and(|b| !b.is_ascii_whitespace())
+        {
+            return Err(self.error(self.pos, "expected `<?php` open tag"));
+        }
+        self.pos += 5;
+        Ok(())
+    }
+
+    /// Offset of the `$` of a variable starting the next token. Variables
+    /// aren't tokens, as no value contains them, but a statement can start
+    /// with one.
+    pub(crate) fn variable_start(&mut self) -> Result<Option<usize>> {
+        self.skip_trivia()?;
+        Ok(Some(self.pos).filter(|_| self.peek_at(0) == Some(b'$')))
+    }
+
+    /// Whether only whitespace is left in the input, which is the case for
+    /// the inline HTML after a `?>` close tag.
+    pub(crate) fn at_blank_end(&self) -> bool {
+        self.input[self.pos..].iter().all(u8::is_ascii_whitespace)
+    }
+
+    /// Skip whitespace and `//`, `#` and `/* */` comments.
+    fn skip_trivia(&mut self) -> Result<()> {
+        loop {
+            match self.peek_at(0) {
+                Some(b) if b.is_ascii_whitespace() => self.pos += 1,
+                Some(b'#') => self.skip_line(),
+                Some(b'/') if self.peek_at(1) == Some(b'/') => self.skip_line(),
+                Some(b'/') if self.peek_at(1) == Some(b'*') => {
+                    let start = self.pos;
+                    match find(&self.input[self.pos + 2..], b"*/") {
+                        Some(end) => self.pos += 2 + end + 2,
+                        None => return Err(self.error(start, "unterminated comment")),
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(b) = self.peek_at(0) {
+            if b == b'\n' {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    #[inline]
+    fn peek_at(&self, n: usize) -> Option<u8> {
+        self.input.get(self.pos + n).copied()
+    }
+
+    fn punct(&mut self, len: usize, token: Token<'de>) -> Token<'de> {
+        self.pos += len;
+        token
+    }
+
+    fn single_quoted(&mut self) -> Result<Token<'de>> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut owned: Option<Vec<u8>> = None;
+        let mut chunk = self.pos;
+        loop {
+            match self.input.get(self.pos) {
+                None => return Err(self.error(start, "unterminated string")),
+                Some(b'\'') => break,
+                Some(b'\\') if matches!(self.peek_at(1), Some(b'\'') | Some(b'\\')) => {
+                    let buf = owned.get_or_insert_with(Vec::new);
+                    buf.extend_from_slice(&self.input[chunk..self.pos]);
+                    buf.push(self.input[self.pos + 1]);
+                    self.pos += 2;
+                    chunk = self.pos;
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+        let rest = &self.input[chunk..self.pos];
+        self.pos += 1;
+        Ok(Token::Str(match owned {
+            Some(mut buf) => {
+                buf.extend_from_slice(rest);
+                Cow::Owned(buf)
+            }
+            None => Cow::Borrowed(rest),
+        }))
+    }
+
+    fn double_quoted(&mut self) -> Result<Token<'de>> {
+        let start = self.pos;
+        self.pos += 1;
+        loop {
+            match self.input.get(self.pos) {
+                None => return Err(self.error(start, "unterminated string")),
+                Some(b'"') => break,
+                Some(b'\\') => self.pos += 2,
+                Some(_) => self.pos += 1,
+            }
+        }
+        let raw = &self.input[start + 1..self.pos];
+        self.pos += 1;
+        self.unescape(raw, start + 1, b'"').map(Token::Str)
+    }
+
+    /// Interpret the escape sequences of the body of a double-quoted string or
+    /// heredoc, which starts at `offset`.
+    fn unescape(&self, raw: &'de [u8], offset: usize, quote: u8) -> Result<Cow<'de, [u8]>> {
+        if let Some(i) = raw
+            .iter()
+            .enumerate()
+            .position(|(i, &b)| b == b'$' && is_interpolation(raw, i))
+        {
+            return Err(self.error(offset + i, "string interpolation is not supported"));
+        }
+        if !raw.contains(&b'\\') {
+            return Ok(Cow::Borrowed(raw));
+        }
+
+        let mut s = Vec::with_capacity(raw.len());
+        let mut i = 0;
+        while i < raw.len() {
+            if raw[i] != b'\\' || i + 1 == raw.len() {
+                s.push(raw[i]);
+                i += 1;
+                continue;
+            }
+            let escape = raw[i + 1];
+            i += 2;
+            match escape {
+                b'n' => s.push(b'\n'),
+                b't' => s.push(b'\t'),
+                b'r' => s.push(b'\r'),
+                b'v' => s.push(0x0b),
+                b'e' => s.push(0x1b),
+                b'f' => s.push(0x0c),
+                b'\\' | b'$' => s.push(escape),
+                b'"' if quote == b'"' => s.push(escape),
+                b'0'..=b'7' => {
+                    let len = raw[i..]
+                        .iter()
+                        .take(2)
+                        .take_while(|b| is_octal(**b))
+                        .count();
+                    let digits = std::str::from_utf8(&raw[i - 1..i + len]).unwrap();
+                    s.push(u32::from_str_radix(digits, 8).unwrap() as u8);
+                    i += len;
+                }
+                b'x' if raw.get(i).is_some_and(u8::is_ascii_hexdigit) => {
+                    let len = raw[i..]
+                        .iter()
+                        .take(2)
+                        .take_while(|b| b.is_ascii_hexdigit())
+                        .count();
+                    let digits = std::str::from_utf8(&raw[i..i + len]).unwrap();
+                    s.push(u8::from_str_radix(digits, 16).unwrap());
+                    i += len;
+                }
+                b'u' if raw.get(i) == Some(&b'{') => {
+                    let c = raw[i + 1..]
+                        .iter()
+                        .position(|&b| b == b'}')
+                        .and_then(|len| std::str::from_utf8(&raw[i + 1..i + 1 + len]).ok())
+                        .filter(|digits| !digits.is_empty())
+                        .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+                        .and_then(std::char::from_u32)
+                        .ok_or_else(|| {
+                            self.error(offset + i - 2, "invalid UTF-8 codepoint escape sequence")
+                        })?;
+                    let mut buf = [0; 4];
+                    s.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                    i += raw[i..].iter().position(|&b| b == b'}').unwrap() + 1;
+                }
+                _ => {
+                    s.push(b'\\');
+                    s.push(escape);
+                }
+            }
+        }
+        Ok(Cow::Owned(s))
+    }
+
+    /// Heredoc or nowdoc, including the flexible syntax of PHP 7.3.
+    fn heredoc(&mut self) -> Result<Token<'de>> {
+        let start = self.pos;
+        self.pos += 3;
+        while matches!(self.peek_at(0), Some(b' ') | Some(b'\t')) {
+            self.pos += 1;
+        }
+        let quote = match self.peek_at(0) {
+            Some(q @ b'\'') | Some(q @ b'"') => {
+                self.pos += 1;
+                Some(q)
+            }
+            _ => None,
+        };
+        let label_start = self.pos;
+        while self
+            .peek_at(0)
+            .is_some_and(|b| is_name_start(b) || b.is_ascii_digit())
+        {
+            self.pos += 1;
+        }
+        let label = &self.input[label_start..self.pos];
+        if label.is_empty() || !is_name_start(label[0]) {
+            return Err(self.error(start, "invalid heredoc label"));
+        }
+        if let Some(q) = quote {
+            if self.peek_at(0) != Some(q) {
+                return Err(self.error(start, "invalid heredoc label"));
+            }
+            self.pos += 1;
+        }
+        if self.peek_at(0) == Some(b'\r') {
+            self.pos += 1;
+        }
+        if self.peek_at(0) != Some(b'\n') {
+            return Err(self.error(start, "expected a newline after the heredoc label"));
+        }
+        self.pos += 1;
+
+        // Find the closing label, alone at the start of a line but for the
+        // indentation.
+        let body_start = self.pos;
+        let mut lines = Vec::new();
+        let indent = loop {
+            let line_start = self.pos;
+            let line_end = self.input[line_start..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(self.input.len(), |len| line_start + len);
+            let line = &self.input[line_start..line_end];
+            let indent_len = line
+                .iter()
+                .take_while(|&&b| b == b' ' || b == b'\t')
+                .count();
+            let rest = &line[indent_len..];
+            if rest.starts_with(label)
+                && !rest
+                    .get(label.len())
+                    .is_some_and(|&b| is_name_start(b) || b.is_ascii_digit())
+            {
+                self.pos = line_start + indent_len + label.len();
+                break &line[..indent_len];
+            }
+            if line_end == self.input.len() {
+                return Err(self.error(start, "unterminated heredoc"));
+            }
+            lines.push((line_start, line));
+            self.pos = line_end + 1;
+        };
+
+        let mut body = Vec::with_capacity(self.pos - body_start);
+        for (i, (line_start, line)) in lines.iter().enumerate() {
+            if i > 0 {
+                body.push(b'\n');
+            }
+            let line = if i + 1 == lines.len() {
+                line.strip_suffix(b"\r").unwrap_or(line)
+            } else {
+                line
+            };
+            match line.strip_prefix(indent) {
+                Some(line) => body.extend_from_slice(line),
+                None if line.iter().all(|&b| b == b' ' || b == b'\t' || b == b'\r') => {}
+                None => {
+                    return Err(self.error(*line_start, "invalid body indentation level"));
+                }
+            }
+        }
+
+        if quote == Some(b'\'') {
+            return Ok(Token::Str(Cow::Owned(body)));
+        }
+        let s = self
+            .unescape(&body, body_start, 0)
+            .map(|s| s.into_owned())?;
+        Ok(Token::Str(Cow::Owned(s)))
+    }
+
+    fn number(&mut self) -> Result<Token<'de>> {
+        let start = self.pos;
+        let radix = match (
+            self.peek_at(0),
+            self.peek_at(1).map(|b| b.to_ascii_lowercase()),
+        ) {
+            (Some(b'0'), Some(b'x')) => 16,
+            (Some(b'0'), Some(b'o')) => 8,
+            (Some(b'0'), Some(b'b')) => 2,
+            _ => 10,
+        };
+        if radix != 10 {
+            self.pos += 2;
+            let digits = self.digits(radix)?;
+            return Ok(match i64::from_str_radix(&digits, radix) {
+                Ok(n) => Token::Int(n),
+                Err(_) if !digits.is_empty() => Token::Float(float_from_radix(&digits, radix)),
+                Err(_) => return Err(self.error(start, "invalid numeric literal")),
+            });
+        }
+
+        let mut text = self.digits(10)?;
+        let mut is_float = false;
+        if self.peek_at(0) == Some(b'.') {
+            is_float = true;
+            self.pos += 1;
+            text.push('.');
+            text.push_str(&self.digits(10)?);
+        }
+        if matches!(self.peek_at(0), Some(b'e') | Some(b'E')) {
+            let sign = matches!(self.peek_at(1), Some(b'+') | Some(b'-')) as usize;
+            if self.peek_at(1 + sign).is_some_and(|b| b.is_ascii_digit()) {
+                is_float = true;
+                text.push('e');
+                if sign == 1 {
+                    text.push(self.input[self.pos + 1] as char);
+                }
+                self.pos += 1 + sign;
+                text.push_str(&self.digits(10)?);
+            }
+        }
+
+        if !is_float && text.len() > 1 && text.starts_with('0') {
+            // Legacy octal literal like `0755`.
+            return match i64::from_str_radix(&text, 8) {
+                Ok(n) => Ok(Token::Int(n)),
+                Err(_) if text.bytes().all(is_octal) => {
+                    Ok(Token::Float(float_from_radix(&text, 8)))
+                }
+                Err(_) => Err(self.error(start, "invalid numeric literal")),
+            };
+        }
+        if !is_float {
+            if let Ok(n) = text.parse() {
+                return Ok(Token::Int(n));
+            }
+        }
+        // Integers overflowing PHP int become floats, like in PHP.
+        text.parse()
+            .map(Token::Float)
+            .map_err(|_| self.error(start, "invalid numeric literal"))
+    }
+
+    /// Read the digits of `radix`, which can be separated by single `_`.
+    fn digits(&mut self, radix: u32) -> Result<String> {
+        let mut digits = String::new();
+        loop {
+            match self.peek_at(0) {
+                Some(b) if (b as char).is_digit(radix) => digits.push(b as char),
+                Some(b'_')
+                    if !digits.is_empty()
+                        && self.peek_at(1).is_some_and(|b| (b as char).is_digit(radix)) => {}
+                Some(b'e') | Some(b'E') if radix == 10 => return Ok(digits),
+                Some(b) if b.is_ascii_alphanumeric() || b == b'_' => {
+                    return Err(self.error(self.pos, "invalid numeric literal"));
+                }
+                _ => return Ok(digits),
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn name(&mut self) -> Token<'de> {
+        let start = self.pos;
+        loop {
+            match self.peek_at(0) {
+                Some(b'\\') if self.peek_at(1).is_some_and(is_name_start) => self.pos += 2,
+                Some(b) if is_name_start(b) || b.is_ascii_digit() => self.pos += 1,
+                _ => break,
+            }
+        }
+        if self.pos == start {
+            // A lone backslash.
+            self.pos += 1;
+        }
+        Token::Name(std::str::from_utf8(&self.input[start..self.pos]).unwrap_or_default())
+    }
+
+    pub(crate) fn error(&self, offset: usize, message: &str) -> Error {
+        syntax_error(self.input, offset, message)
+    }
+}
+
+#[inline]
+fn is_name_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_' || b >= 0x80
+}
+
+#[inline]
+fn is_octal(b: u8) -> bool {
+    (b'0'..=b'7').contains(&b)
+}
+
+/// Whether the `$` at `i` starts a variable interpolated in a double-quoted
+/// string, like `$a`, `{$a}` or `${a}`, rather than being a literal dollar
+/// sign.
+fn is_interpolation(raw: &[u8], i: usize) -> bool {
+    let escaped = raw[..i].iter().rev().take_while(|&&b| b == b'\\').count() % 2 == 1;
+    let variable = raw
+        .get(i + 1)
+        .is_some_and(|&b| is_name_start(b) || b == b'{');
+    let braced = i > 0 && raw[i - 1] == b'{' && raw.get(i + 1).is_some_and(|&b| is_name_start(b));
+    !escaped && (variable || braced)
+}
+
+fn float_from_radix(digits: &str, radix: u32) -> f64 {
+    digits.chars().fold(0.0, |n, digit| {
+        n * radix as f64 + digit.to_digit(radix).unwrap() as f64
+    })
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Syntax error at the byte `offset` of `input`.
+pub(crate) fn syntax_error(input: &[u8], offset: usize, message: &str) -> Error {
+    let (line, column, snippet) = position(input, offset);
+    Error::Syntax {
+        message: message.to_owned(),
+        line,
+        column,
+        offset,
+        snippet,
+    }
+}
+
+/// Attach the position of the byte `offset` of `input` to an error of the
+/// value there, unless it already has one.
+pub(crate) fn error_at(input: &[u8], offset: usize, err: Error) -> Error {
+    match err {
+        Error::Syntax { .. } | Error::AtPosition { .. } | Error::Io(_) => err,
+        err => {
+            let (line, column, snippet) = position(input, offset);
+            Error::AtPosition {
+                line,
+                column,
+                offset,
+                snippet,
+                source: Box::new(err),
+            }
+        }
+    }
+}
+
+/// Line, column and snippet of the byte `offset` of `input`.
+fn position(input: &[u8], offset: usize) -> (usize, usize, String) {
+    let offset = offset.min(input.len());
+    let line_start = input[..offset]
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |pos| pos + 1);
+    let line_end = input[offset..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(input.len(), |len| offset + len);
+    let line = input[..offset].iter().filter(|&&b| b == b'\n').count() + 1;
+    let before = String::from_utf8_lossy(&input[line_start..offset]);
+    let column = before.chars().count() + 1;
+
+    let text = String::from_utf8_lossy(&input[line_start..line_end]);
+    let number = line.to_string();
+    // Keep the tabs so that the caret is aligned with the text.
+    let pad: String = before
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let snippet = format!(
+        "{number} | {text}\n{gutter} | {pad}^",
+        number = number,
+        text = text.trim_end_matches('\r'),
+        gutter = " ".repeat(number.len()),
+        pad = pad,
+    );
+    (line, column, snippet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(input: &str) -> Vec<Token<'_>> {
+        let mut lexer = Lexer::new(input.as_bytes());
+        let mut tokens = Vec::new();
+        loop {
+            match lexer.next_token().unwrap().0 {
+                Token::Eof => return tokens,
+                token => tokens.push(token),
+            }
+        }
+    }
+
+    #[test]
+    fn var_export_tokens() {
+        assert_eq!(
+            tokens("array (\n  'a\\'b' => -1.5E+25,\n  0 => \\Foo\\Bar::__set_state(NULL),\n)"),
+            vec![
+                Token::Name("array"),
+                Token::LParen,
+                Token::Str(Cow::Borrowed(b"a'b")),
+                Token::Arrow,
+                Token::Minus,
+                Token::Float(1.5e25),
+                Token::Comma,
+                Token::Int(0),
+                Token::Arrow,
+                Token::Name("\\Foo\\Bar"),
+                Token::DoubleColon,
+                Token::Name("__set_state"),
+                Token::LParen,
+                Token::Name("NULL"),
+                Token::RParen,
+                Token::Comma,
+                Token::RParen,
+            ]
+        );
+    }
+
+    #[test]
+    fn single_quoted() {
+        assert_eq!(
+            tokens(r"'a\nb' 'c\\d' '\''"),
+            vec![
+                Token::Str(Cow::Borrowed(br"a\nb")),
+                Token::Str(Cow::Owned(br"c\d".to_vec())),
+                Token::Str(Cow::Owned(b"'".to_vec())),
+            ]
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(
+            tokens("0 42 1.0 .5 9223372036854775808"),
+            vec![
+                Token::Int(0),
+                Token::Int(42),
+                Token::Float(1.0),
+                Token::Float(0.5),
+                Token::Float(9223372036854775808.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn short_arrays_and_comments() {
+        assert_eq!(
+            tokens("[1, /* two */ 2] // three\n# four\n"),
+            vec![
+                Token::LBracket,
+                Token::Int(1),
+                Token::Comma,
+                Token::Int(2),
+                Token::RBracket,
+            ]
+        );
+    }
+
+    #[test]
+    fn double_quoted() {
+        assert_eq!(
+            tokens(r#""plain" "a\n\"\\\$\e\q" "\101\x41\u{e9}" "$ {$""#),
+            vec![
+                Token::Str(Cow::Borrowed(b"plain")),
+                Token::Str(Cow::Owned(b"a\n\"\\$\x1b\\q".to_vec())),
+                Token::Str(Cow::Owned("AA\u{e9}".as_bytes().to_vec())),
+                Token::Str(Cow::Borrowed(b"$ {$")),
+            ]
+        );
+        assert!(Lexer::new(br#""$a""#).next_token().is_err());
+        assert!(Lexer::new(br#""{$a}""#).next_token().is_err());
+        assert!(Lexer::new(br#""${a}""#).next_token().is_err());
+        assert!(Lexer::new(br#""\${a}""#).next_token().is_ok());
+        assert!(Lexer::new(br#""\u{110000}""#).next_token().is_err());
+    }
+
+    #[test]
+    fn heredoc() {
+        assert_eq!(
+            tokens("<<<EOT\na\\t\"b\"\nEOT"),
+            vec![Token::Str(Cow::Owned(b"a\t\"b\"".to_vec()))]
+        );
+        assert_eq!(
+            tokens("<<<\"EOT\"\n  a\n\n    b\r\n  EOT,"),
+            vec![Token::Str(Cow::Owned(b"a\n\n  b".to_vec())), Token::Comma]
+        );
+        assert_eq!(
+            tokens("<<<'EOT'\n  $a\\n\n  EOT"),
+            vec![Token::Str(Cow::Owned(b"$a\\n".to_vec()))]
+        );
+        assert!(Lexer::new(b"<<<EOT\n  a\n b\n  EOT").next_token().is_err());
+        assert!(Lexer::new(b"<<<EOT\na\n").next_token().is_err());
+    }
+
+    #[test]
+    fn prefixed_numbers() {
+        assert_eq!(
+            tokens("0x1A 0XfF 0755 0o17 0b101 1_000 1e3 1E-2 0x8000000000000000"),
+            vec![
+                Token::Int(26),
+                Token::Int(255),
+                Token::Int(0o755),
+                Token::Int(0o17),
+                Token::Int(5),
+                Token::Int(1000),
+                Token::Float(1000.0),
+                Token::Float(0.01),
+                Token::Float(9223372036854775808.0),
+            ]
+        );
+        assert!(Lexer::new(b"0x").next_token().is_err());
+        assert!(Lexer::new(b"089").next_token().is_err());
+        assert!(Lexer::new(b"1__0").next_token().is_err());
+        assert!(Lexer::new(b"12ab").next_token().is_err());
+    }
+
+    #[test]
+    fn error_position() {
+        let mut lexer = Lexer::new("array(\n  ?".as_bytes());
+        lexer.next_token().unwrap();
+        lexer.next_token().unwrap();
+        let err = lexer.next_token().unwrap_err();
+        assert_eq!((err.line(), err.column()), (Some(2), Some(3)));
+    }
+}
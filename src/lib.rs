@@ -47,16 +47,51 @@
 //! )
 //! ```
 //!
-//! ## Limitation
+//! The output can be read back with [`from_str`], which also accepts the output of PHP's own
+//! `var_export()`:
 //!
-//! Now only support serialization, deserialization will support in future.
+//! ```rust
+//! use serde_derive::Deserialize;
+//!
+//! #[derive(Deserialize, Debug, PartialEq)]
+//! struct Foo {
+//!     names: Vec<String>,
+//!     nums: Vec<i32>,
+//! }
+//!
+//! let foo: Foo = serde_var_export::from_str(
+//!     "array (
+//!   'names' =>
+//!   array (
+//!     0 => 'hello',
+//!     1 => 'world',
+//!   ),
+//!   'nums' => array(1, 2, 3),
+//! )",
+//! )
+//! .unwrap();
+//! assert_eq!(foo.nums, vec![1, 2, 3]);
+//! ```
+//!
+//! Config files like `<?php return [...];` can be read with [`from_php_file`].
 //!
 //! ## License
 //!
 //! The Unlicense.
 
+mod array;
+mod de;
 mod error;
+mod lexer;
+mod macros;
+mod parse;
+mod raw;
 mod ser;
+mod value;
 
-pub use error::{Error, Result};
+pub use array::{ArrayKey, PhpArray};
+pub use de::{from_php_file, from_php_file_str, from_reader, from_slice, from_str, Deserializer};
+pub use error::{Error, ErrorKind, Result};
+pub use raw::RawPhp;
 pub use ser::{to_string, to_vec, to_writer, Serializer};
+pub use value::{from_value, to_value, Value};
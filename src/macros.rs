@@ -0,0 +1,107 @@
+/// Construct a [`Value`](crate::Value) from PHP-like array syntax.
+///
+/// Arrays are written with `[...]`, entries with `key => value` or just
+/// `value` to append at the next index like PHP does. Keys are anything
+/// convertible into an [`ArrayKey`](crate::ArrayKey) and values are nested
+/// arrays, `null`, or any Rust expression implementing `Serialize`. Strings use
+/// Rust's double quotes, since `'db'` isn't a Rust token.
+///
+/// ```rust
+/// use serde_var_export::php;
+///
+/// let port = 3306;
+/// let config = php!([
+///     "db" => ["host" => "localhost", "port" => port],
+///     "debug" => false,
+///     "hosts" => ["a", "b"],
+///     "cache" => null,
+/// ]);
+/// assert_eq!(
+///     serde_var_export::to_string(&config).unwrap(),
+///     "array(
+///   'db' =>
+///   array(
+///     'host' => 'localhost',
+///     'port' => 3306,
+///   ),
+///   'debug' => false,
+///   'hosts' =>
+///   array(
+///     0 => 'a',
+///     1 => 'b',
+///   ),
+///   'cache' => NULL,
+/// )"
+/// );
+/// ```
+///
+/// Each entry of an array takes one level of the macro recursion limit, so an
+/// array with more than about 120 entries needs a higher
+/// `#![recursion_limit]`.
+///
+/// # Panics
+///
+/// An interpolated expression which can't be converted with
+/// [`to_value`](crate::to_value), like a map with array keys, panics.
+#[macro_export]
+macro_rules! php {
+    ($($php:tt)+) => {
+        $crate::php_internal!($($php)+)
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! php_internal {
+    // Entries of an array, one per step. Nested arrays and `null` are matched
+    // before the expressions, which can't contain `=>` or be matched again.
+    (@entries $array:ident) => {};
+
+    (@entries $array:ident null $(, $($rest:tt)*)?) => {
+        $array.push($crate::Value::Null);
+        $crate::php_internal!(@entries $array $($($rest)*)?);
+    };
+
+    (@entries $array:ident [$($value:tt)*] $(, $($rest:tt)*)?) => {
+        $array.push($crate::php_internal!([$($value)*]));
+        $crate::php_internal!(@entries $array $($($rest)*)?);
+    };
+
+    (@entries $array:ident $key:expr => null $(, $($rest:tt)*)?) => {
+        $array.insert($key, $crate::Value::Null);
+        $crate::php_internal!(@entries $array $($($rest)*)?);
+    };
+
+    (@entries $array:ident $key:expr => [$($value:tt)*] $(, $($rest:tt)*)?) => {
+        $array.insert($key, $crate::php_internal!([$($value)*]));
+        $crate::php_internal!(@entries $array $($($rest)*)?);
+    };
+
+    (@entries $array:ident $key:expr => $value:expr $(, $($rest:tt)*)?) => {
+        $array.insert($key, $crate::php_internal!($value));
+        $crate::php_internal!(@entries $array $($($rest)*)?);
+    };
+
+    (@entries $array:ident $value:expr $(, $($rest:tt)*)?) => {
+        $array.push($crate::php_internal!($value));
+        $crate::php_internal!(@entries $array $($($rest)*)?);
+    };
+
+    (null) => {
+        $crate::Value::Null
+    };
+
+    ([]) => {
+        $crate::Value::Array($crate::PhpArray::new())
+    };
+
+    ([$($entries:tt)+]) => {{
+        let mut array = $crate::PhpArray::new();
+        $crate::php_internal!(@entries array $($entries)+);
+        $crate::Value::Array(array)
+    }};
+
+    ($other:expr) => {
+        $crate::to_value(&$other).unwrap()
+    };
+}
@@ -0,0 +1,545 @@
+use crate::array::NextIndex;
+use crate::error::{Error, Result};
+use crate::lexer::{Lexer, Token};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+/// PHP value parsed from the input, with the offset where it starts.
+#[derive(Debug)]
+pub(crate) struct Expr<'de> {
+    pub(crate) kind: ExprKind<'de>,
+    pub(crate) offset: usize,
+}
+
+#[derive(Debug)]
+pub(crate) enum ExprKind<'de> {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(Cow<'de, [u8]>),
+    Array(Vec<(Key<'de>, Expr<'de>)>),
+    /// Object written like `\Foo::__set_state(array(...))`, with the class
+    /// name without the leading backslash.
+    Object {
+        class: &'de str,
+        properties: Vec<(Key<'de>, Expr<'de>)>,
+    },
+    /// Case of a PHP 8.1 enum written like `\Suit::Hearts`, with the class
+    /// name without the leading backslash.
+    EnumCase {
+        class: &'de str,
+        case: &'de str,
+    },
+    /// Expression of a [`Value::Raw`](crate::Value::Raw), which isn't parsed.
+    Raw(&'de str),
+}
+
+/// Array key normalized like PHP does.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Key<'de> {
+    Int(i64),
+    Str(Cow<'de, [u8]>),
+}
+
+impl<'de> Key<'de> {
+    fn from_str(s: Cow<'de, [u8]>) -> Self {
+        match decimal_int(&s) {
+            Some(n) => Key::Int(n),
+            None => Key::Str(s),
+        }
+    }
+}
+
+impl fmt::Display for Key<'_> {
+    /// Write the key like in PHP code.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Key::Int(n) => write!(f, "{}", n),
+            Key::Str(s) => write!(
+                f,
+                "'{}'",
+                String::from_utf8_lossy(s)
+                    .replace('\\', "\\\\")
+                    .replace('\'', "\\'")
+            ),
+        }
+    }
+}
+
+/// Integer value of `s` if it is in canonical decimal form, like PHP uses to
+/// turn string keys into integer keys.
+fn decimal_int(s: &[u8]) -> Option<i64> {
+    let digits = s.strip_prefix(b"-").unwrap_or(s);
+    match digits {
+        [b'0'] if digits.len() == s.len() => Some(0),
+        [b'1'..=b'9', rest @ ..] if rest.iter().all(u8::is_ascii_digit) => {
+            std::str::from_utf8(s).ok()?.parse().ok()
+        }
+        _ => None,
+    }
+}
+
+/// Whether `name` is the case-insensitive constant `keyword`, optionally fully
+/// qualified like `\true`.
+fn keyword(name: &str, keyword: &str) -> bool {
+    name.strip_prefix('\\')
+        .unwrap_or(name)
+        .eq_ignore_ascii_case(keyword)
+}
+
+/// Class of the generic objects of PHP, like the ones of `json_decode()`.
+pub(crate) const STD_CLASS: &str = "stdClass";
+
+/// Whether `class` is `stdClass`, whose name is case-insensitive like all class
+/// names.
+fn is_std_class(class: &str) -> bool {
+    class.eq_ignore_ascii_case(STD_CLASS)
+}
+
+/// Maximum nesting of values, like arrays or signs, to not overflow the
+/// stack.
+const RECURSION_LIMIT: usize = 128;
+
+pub(crate) struct Parser<'de> {
+    lexer: Lexer<'de>,
+    peeked: Option<(Token<'de>, usize)>,
+    remaining_depth: usize,
+}
+
+impl<'de> Parser<'de> {
+    pub(crate) fn new(input: &'de [u8]) -> Self {
+        Self {
+            lexer: Lexer::new(input),
+            peeked: None,
+            remaining_depth: RECURSION_LIMIT,
+        }
+    }
+
+    pub(crate) fn input(&self) -> &'de [u8] {
+        self.lexer.input()
+    }
+
+    fn peek(&mut self) -> Result<&Token<'de>> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lexer.next_token()?);
+        }
+        Ok(&self.peeked.as_ref().unwrap().0)
+    }
+
+    fn next(&mut self) -> Result<(Token<'de>, usize)> {
+        match self.peeked.take() {
+            Some(token) => Ok(token),
+            None => self.lexer.next_token(),
+        }
+    }
+
+    fn expect(&mut self, expected: Token<'de>) -> Result<usize> {
+        let (token, offset) = self.next()?;
+        if token != expected {
+            return Err(self.unexpected(&token, offset));
+        }
+        Ok(offset)
+    }
+
+    fn unexpected(&self, token: &Token, offset: usize) -> Error {
+        self.error(offset, &format!("unexpected {}", token.describe()))
+    }
+
+    pub(crate) fn error(&self, offset: usize, message: &str) -> Error {
+        self.lexer.error(offset, message)
+    }
+
+    /// Fail unless all the input has been consumed.
+    pub(crate) fn end(&mut self) -> Result<()> {
+        let (token, offset) = self.next()?;
+        match token {
+            Token::Eof => Ok(()),
+            token => Err(self.unexpected(&token, offset)),
+        }
+    }
+
+    /// Skip the open tag and the statements of a PHP file which don't produce
+    /// a value, up to the `return` of the value.
+    pub(crate) fn begin_file(&mut self) -> Result<()> {
+        self.lexer.open_tag()?;
+        loop {
+            if let Some(offset) = self.lexer.variable_start()? {
+                return Err(self.error(offset, "unsupported statement, expected `return`"));
+            }
+            let (token, offset) = self.next()?;
+            match token {
+                Token::Name(name) if name.eq_ignore_ascii_case("return") => return Ok(()),
+                Token::Name(name) if name.eq_ignore_ascii_case("declare") => self.skip_declare()?,
+                Token::Name(name) if name.eq_ignore_ascii_case("namespace") => {
+                    self.skip_namespace()?
+                }
+                Token::Name(name) if name.eq_ignore_ascii_case("use") => self.skip_use()?,
+                Token::Semicolon => {}
+                Token::Name(name) => {
+                    return Err(self.error(
+                        offset,
+                        &format!("unsupported statement `{}`, expected `return`", name),
+                    ))
+                }
+                Token::Eof => return Err(self.error(offset, "missing `return` statement")),
+                token => return Err(self.unexpected(&token, offset)),
+            }
+        }
+    }
+
+    /// Fail unless the returned value is the end of the PHP file.
+    pub(crate) fn end_file(&mut self) -> Result<()> {
+        let (mut token, mut offset) = self.next()?;
+        if token == Token::Semicolon {
+            (token, offset) = self.next()?;
+        }
+        match token {
+            Token::Eof => Ok(()),
+            Token::CloseTag if self.lexer.at_blank_end() => Ok(()),
+            Token::CloseTag => Err(self.error(offset, "unexpected output after `?>`")),
+            token => Err(self.unexpected(&token, offset)),
+        }
+    }
+
+    /// Skip `declare(strict_types=1);`, after the `declare` keyword.
+    fn skip_declare(&mut self) -> Result<()> {
+        self.expect(Token::LParen)?;
+        loop {
+            let (token, offset) = self.next()?;
+            match token {
+                Token::Name(_) => {}
+                token => return Err(self.unexpected(&token, offset)),
+            }
+            self.expect(Token::Equals)?;
+            self.parse_value()?;
+            let (token, offset) = self.next()?;
+            match token {
+                Token::Comma => {}
+                Token::RParen => break,
+                token => return Err(self.unexpected(&token, offset)),
+            }
+        }
+        let (token, offset) = self.next()?;
+        match token {
+            Token::Semicolon => Ok(()),
+            Token::LBrace => Err(self.error(offset, "unsupported `declare` block")),
+            token => Err(self.unexpected(&token, offset)),
+        }
+    }
+
+    /// Skip `namespace App\Config;`, after the `namespace` keyword.
+    fn skip_namespace(&mut self) -> Result<()> {
+        let (token, offset) = self.next()?;
+        let token = match token {
+            Token::Name(_) => self.next()?,
+            token => (token, offset),
+        };
+        match token {
+            (Token::Semicolon, _) => Ok(()),
+            (Token::LBrace, offset) => Err(self.error(offset, "unsupported `namespace` block")),
+            (token, offset) => Err(self.unexpected(&token, offset)),
+        }
+    }
+
+    /// Skip `use` imports, including grouped ones like `use App\{Foo, Bar};`,
+    /// after the `use` keyword.
+    fn skip_use(&mut self) -> Result<()> {
+        loop {
+            let (token, offset) = self.next()?;
+            match token {
+                Token::Semicolon => return Ok(()),
+                Token::Name(_) | Token::Comma | Token::LBrace | Token::RBrace => {}
+                token => return Err(self.unexpected(&token, offset)),
+            }
+        }
+    }
+
+    pub(crate) fn parse_value(&mut self) -> Result<Expr<'de>> {
+        let mut expr = self.parse_unary()?;
+        loop {
+            let kind = match self.peek()? {
+                Token::Dot => {
+                    let (_, offset) = self.next()?;
+                    match (expr.kind, self.parse_unary()?.kind) {
+                        (ExprKind::Str(left), ExprKind::Str(right)) => {
+                            let mut s = left.into_owned();
+                            s.extend_from_slice(&right);
+                            ExprKind::Str(Cow::Owned(s))
+                        }
+                        _ => return Err(self.error(offset, "only strings can be concatenated")),
+                    }
+                }
+                // `var_export(PHP_INT_MIN)` is `-9223372036854775807-1`.
+                Token::Minus => {
+                    let (_, offset) = self.next()?;
+                    match (expr.kind, self.parse_unary()?.kind) {
+                        (ExprKind::Int(left), ExprKind::Int(right)) => {
+                            match left.checked_sub(right) {
+                                Some(n) => ExprKind::Int(n),
+                                None => ExprKind::Float(left as f64 - right as f64),
+                            }
+                        }
+                        _ => return Err(self.error(offset, "only integers can be subtracted")),
+                    }
+                }
+                _ => return Ok(expr),
+            };
+            expr = Expr {
+                kind,
+                offset: expr.offset,
+            };
+        }
+    }
+
+    /// Parse a value without concatenation. Every rule which nests values goes
+    /// through here, so this is where the nesting is limited.
+    fn parse_unary(&mut self) -> Result<Expr<'de>> {
+        if self.remaining_depth == 0 {
+            self.peek()?;
+            let offset = self.peeked.as_ref().map_or(0, |(_, offset)| *offset);
+            return Err(self.error(offset, "recursion limit exceeded"));
+        }
+        self.remaining_depth -= 1;
+        let expr = self.parse_signed();
+        self.remaining_depth += 1;
+        expr
+    }
+
+    fn parse_signed(&mut self) -> Result<Expr<'de>> {
+        let negative = match self.peek()? {
+            Token::Minus => true,
+            Token::Plus => false,
+            _ => return self.parse_primary(),
+        };
+        let (_, offset) = self.next()?;
+        let expr = self.parse_unary()?;
+        let kind = match expr.kind {
+            ExprKind::Int(n) if negative => match n.checked_neg() {
+                Some(n) => ExprKind::Int(n),
+                None => ExprKind::Float(-(n as f64)),
+            },
+            ExprKind::Float(n) if negative => ExprKind::Float(-n),
+            kind @ ExprKind::Int(_) | kind @ ExprKind::Float(_) => kind,
+            _ => return Err(self.error(offset, "only numbers can have a sign")),
+        };
+        Ok(Expr { kind, offset })
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr<'de>> {
+        let (token, offset) = self.next()?;
+        let kind = match token {
+            Token::Int(n) => ExprKind::Int(n),
+            Token::Float(n) => ExprKind::Float(n),
+            Token::Str(s) => ExprKind::Str(s),
+            Token::Name(name) if keyword(name, "null") => ExprKind::Null,
+            Token::Name(name) if keyword(name, "true") => ExprKind::Bool(true),
+            Token::Name(name) if keyword(name, "false") => ExprKind::Bool(false),
+            Token::Name("NAN") => ExprKind::Float(f64::NAN),
+            Token::Name("INF") => ExprKind::Float(f64::INFINITY),
+            Token::Name(name) if name.eq_ignore_ascii_case("array") => {
+                self.expect(Token::LParen)?;
+                ExprKind::Array(self.parse_array(Token::RParen)?)
+            }
+            Token::LBracket => ExprKind::Array(self.parse_array(Token::RBracket)?),
+            Token::LParen => self.parse_object_cast(offset)?,
+            Token::Name(class) if *self.peek()? == Token::DoubleColon => {
+                self.next()?;
+                self.parse_class_member(class)?
+            }
+            token => return Err(self.unexpected(&token, offset)),
+        };
+        Ok(Expr { kind, offset })
+    }
+
+    /// Parse `(object) array(...)`, after the `(`.
+    fn parse_object_cast(&mut self, offset: usize) -> Result<ExprKind<'de>> {
+        let (token, name_offset) = self.next()?;
+        match token {
+            Token::Name(name) if name.eq_ignore_ascii_case("object") => {}
+            Token::Name(_) => return Err(self.error(name_offset, "unsupported cast")),
+            token => return Err(self.unexpected(&token, name_offset)),
+        }
+        self.expect(Token::RParen)?;
+        match self.parse_unary()?.kind {
+            ExprKind::Array(properties) => Ok(ExprKind::Object {
+                class: STD_CLASS,
+                properties,
+            }),
+            _ => Err(self.error(offset, "only arrays can be cast to object")),
+        }
+    }
+
+    /// Parse `__set_state(array(...))` or an enum case, after `\Foo::`.
+    fn parse_class_member(&mut self, class: &'de str) -> Result<ExprKind<'de>> {
+        let (token, offset) = self.next()?;
+        let class = class.trim_start_matches('\\');
+        match token {
+            Token::Name(name) if *self.peek()? != Token::LParen => {
+                return Ok(ExprKind::EnumCase { class, case: name })
+            }
+            Token::Name(name) if name.eq_ignore_ascii_case("__set_state") => {}
+            Token::Name(_) => return Err(self.error(offset, "unsupported method call")),
+            token => return Err(self.unexpected(&token, offset)),
+        }
+        self.expect(Token::LParen)?;
+        let expr = self.parse_value()?;
+        let properties = match expr.kind {
+            ExprKind::Array(entries) => entries,
+            _ => return Err(self.error(expr.offset, "expected array of properties")),
+        };
+        self.expect(Token::RParen)?;
+        Ok(ExprKind::Object {
+            class: if is_std_class(class) {
+                STD_CLASS
+            } else {
+                class
+            },
+            properties,
+        })
+    }
+
+    /// Parse the entries of `array(...)` or `[...]`, after the opening token,
+    /// up to `close`.
+    fn parse_array(&mut self, close: Token<'de>) -> Result<Vec<(Key<'de>, Expr<'de>)>> {
+        let mut entries = Vec::new();
+        let mut positions = HashMap::new();
+        let mut next_index = NextIndex::default();
+        loop {
+            if *self.peek()? == close {
+                self.next()?;
+                return Ok(entries);
+            }
+
+            let expr = self.parse_value()?;
+            let (key, value) = if let Token::Arrow = self.peek()? {
+                self.next()?;
+                (self.parse_key(expr)?, self.parse_value()?)
+            } else {
+                match next_index.get(|n| positions.contains_key(&Key::Int(n))) {
+                    Some(n) => (Key::Int(n), expr),
+                    None => return Err(self.error(
+                        expr.offset,
+                        "cannot add element to the array as the next element is already occupied",
+                    )),
+                }
+            };
+            if let Key::Int(n) = key {
+                next_index.insert(n);
+            }
+            // A duplicate key overwrites the previous value in place.
+            match positions.get(&key) {
+                Some(&i) => entries[i] = (key, value),
+                None => {
+                    positions.insert(key.clone(), entries.len());
+                    entries.push((key, value));
+                }
+            }
+
+            let (token, offset) = self.next()?;
+            match token {
+                Token::Comma => {}
+                token if token == close => return Ok(entries),
+                token => return Err(self.unexpected(&token, offset)),
+            }
+        }
+    }
+
+    fn parse_key(&self, expr: Expr<'de>) -> Result<Key<'de>> {
+        Ok(match expr.kind {
+            ExprKind::Null => Key::Str(Cow::Borrowed(b"")),
+            ExprKind::Bool(b) => Key::Int(b as i64),
+            ExprKind::Int(n) => Key::Int(n),
+            ExprKind::Float(n) => Key::Int(n as i64),
+            ExprKind::Str(s) => Key::from_str(s),
+            ExprKind::Array(_)
+            | ExprKind::Object { .. }
+            | ExprKind::EnumCase { .. }
+            | ExprKind::Raw(_) => return Err(self.error(expr.offset, "illegal offset type")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(input: &str) -> Vec<Key<'_>> {
+        match Parser::new(input.as_bytes()).parse_value().unwrap().kind {
+            ExprKind::Array(entries) => entries.into_iter().map(|(key, _)| key).collect(),
+            kind => panic!("not an array: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn array_keys() {
+        assert_eq!(
+            keys("array('a', 5 => 'b', 'c', '7' => 'd', '07' => 'e', true => 'f', 1.5 => 'g')"),
+            vec![
+                Key::Int(0),
+                Key::Int(5),
+                Key::Int(6),
+                Key::Int(7),
+                Key::Str(Cow::Borrowed(b"07")),
+                Key::Int(1),
+            ]
+        );
+        assert_eq!(
+            keys("array(-5 => 'a', 'b', -10 => 'c', 'd')"),
+            vec![Key::Int(-5), Key::Int(-4), Key::Int(-10), Key::Int(-3)]
+        );
+        let err = Parser::new(b"array(9223372036854775807 => 'a', 'b')")
+            .parse_value()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot add element to the array as the next element is already occupied at line 1 column 35"
+        );
+    }
+
+    #[test]
+    fn decimal_int() {
+        assert_eq!(super::decimal_int(b"0"), Some(0));
+        assert_eq!(super::decimal_int(b"-12"), Some(-12));
+        assert_eq!(super::decimal_int(b"-0"), None);
+        assert_eq!(super::decimal_int(b"012"), None);
+        assert_eq!(super::decimal_int(b"1a"), None);
+        assert_eq!(super::decimal_int(b"99999999999999999999"), None);
+    }
+
+    #[test]
+    fn recursion_limit() {
+        let input = "array(".repeat(RECURSION_LIMIT) + &")".repeat(RECURSION_LIMIT);
+        assert!(Parser::new(input.as_bytes()).parse_value().is_ok());
+
+        let input = "array(".repeat(RECURSION_LIMIT + 1);
+        let err = Parser::new(input.as_bytes()).parse_value().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "recursion limit exceeded at line 1 column 769"
+        );
+    }
+
+    #[test]
+    fn sign_recursion_limit() {
+        let input = "-".repeat(RECURSION_LIMIT - 1) + "1";
+        assert!(Parser::new(input.as_bytes()).parse_value().is_ok());
+
+        let input = "-".repeat(200_000) + "1";
+        let err = Parser::new(input.as_bytes()).parse_value().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "recursion limit exceeded at line 1 column 129"
+        );
+    }
+
+    #[test]
+    fn concat() {
+        let expr = Parser::new(b"'a' . 'b' . 'c'").parse_value().unwrap();
+        match expr.kind {
+            ExprKind::Str(s) => assert_eq!(&*s, b"abc"),
+            kind => panic!("not a string: {:?}", kind),
+        }
+    }
+}
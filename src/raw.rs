@@ -0,0 +1,88 @@
+use serde::{Serialize, Serializer};
+
+use crate::error::Result;
+use std::fmt;
+
+/// Newtype struct name recognized by the serializer to write a [`RawPhp`]
+/// verbatim.
+pub(crate) const TOKEN: &str = "$serde_var_export::private::RawPhp";
+
+/// PHP expression which is written verbatim instead of as a literal.
+///
+/// ```rust
+/// use serde_var_export::RawPhp;
+/// use std::collections::BTreeMap;
+///
+/// let mut config = BTreeMap::new();
+/// config.insert("host", RawPhp::call("env", &("DB_HOST", "localhost")).unwrap());
+/// config.insert("storage", RawPhp::new("__DIR__ . '/storage'"));
+/// config.insert("errmode", RawPhp::class_constant("PDO", "ATTR_ERRMODE"));
+///
+/// assert_eq!(
+///     serde_var_export::to_string(&config).unwrap(),
+///     r#"array(
+///   'errmode' => \PDO::ATTR_ERRMODE,
+///   'host' => env('DB_HOST', 'localhost'),
+///   'storage' => __DIR__ . '/storage',
+/// )"#
+/// );
+/// ```
+///
+/// Other serializers see a newtype struct containing the expression string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RawPhp(String);
+
+impl RawPhp {
+    pub fn new(expr: impl Into<String>) -> Self {
+        RawPhp(expr.into())
+    }
+
+    /// Reference to the global constant `name`, like `PHP_EOL`.
+    pub fn constant(name: &str) -> Self {
+        RawPhp(name.to_owned())
+    }
+
+    /// Reference to the constant `name` of `class`, like `\PDO::ATTR_ERRMODE`.
+    ///
+    /// The class name is made fully qualified if it isn't already.
+    pub fn class_constant(class: &str, name: &str) -> Self {
+        let class = class.trim_start_matches('\\');
+        RawPhp(format!("\\{}::{}", class, name))
+    }
+
+    /// Call of `function` with `args` serialized as PHP values.
+    ///
+    /// A tuple or a sequence is spread into several arguments, any other value
+    /// is a single argument, so an array argument has to be wrapped in a tuple
+    /// like `(vec![1, 2],)`. `()` means no arguments.
+    pub fn call<T>(function: &str, args: &T) -> Result<Self>
+    where
+        T: ?Sized + Serialize,
+    {
+        let args = crate::ser::to_call_args(args)?;
+        Ok(RawPhp(format!("{}({})", function, args)))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl fmt::Display for RawPhp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for RawPhp {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(TOKEN, &self.0)
+    }
+}
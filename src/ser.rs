@@ -1,6 +1,8 @@
 use serde::{ser, Serialize};
 
 use crate::error::{Error, Result};
+use crate::raw;
+use crate::value;
 use std::io::Write;
 
 const INDENT: &'static [u8] = b"  ";
@@ -23,6 +25,17 @@ where
     Ok(serializer.writer)
 }
 
+/// Serialize `args` as the comma separated arguments of a function call.
+pub(crate) fn to_call_args<T: ?Sized>(args: &T) -> Result<String>
+where
+    T: Serialize,
+{
+    let mut serializer = Serializer::new(Vec::new());
+    serializer.call_args = true;
+    args.serialize(&mut serializer)?;
+    Ok(String::from_utf8(serializer.writer)?)
+}
+
 pub fn to_writer<W, T: ?Sized>(writer: W, value: &T) -> Result<()>
 where
     W: Write,
@@ -45,55 +58,660 @@ impl<'a, W: Write> SeqSerializer<'a, W> {
         Self { ser, idx: 0 }
     }
 
+    #[cfg_attr(not(test), allow(dead_code))]
     #[inline]
     fn as_mut_writer(&mut self) -> &mut W {
         &mut self.ser.writer
     }
 }
 
+/// Array or scalar kept in memory until its enclosing array is complete, so
+/// that the layout can depend on its size.
+enum Node {
+    Scalar(Vec<u8>),
+    Array {
+        open: Vec<u8>,
+        entries: Vec<(Vec<u8>, Node)>,
+        close: &'static [u8],
+    },
+}
+
+/// Struct field, map key or sequence index leading to the value being
+/// serialized.
+enum PathSegment {
+    Key(String),
+    Index(String),
+}
+
+/// Array under construction while buffering.
+struct Frame {
+    key: Option<Vec<u8>>,
+    open: Vec<u8>,
+    entries: Vec<(Vec<u8>, Node)>,
+    close: &'static [u8],
+}
+
+const ARRAY_OPEN: &[u8] = b"array(";
+const ARRAY_CLOSE: &[u8] = b")";
+
+/// Opening and closing of an object of `class` around the array of its
+/// properties, like PHP 7.3+ writes them.
+fn object_delimiters(class: &str) -> (Vec<u8>, &'static [u8]) {
+    let class = class.trim_start_matches('\\');
+    if class.eq_ignore_ascii_case("stdClass") {
+        (b"(object) array(".to_vec(), b")")
+    } else {
+        (
+            format!("\\{}::__set_state(array(", class).into_bytes(),
+            b"))",
+        )
+    }
+}
+
 pub struct Serializer<W: Write> {
     writer: W,
     current_ident: usize,
+    line_width: Option<usize>,
+    align_arrows: bool,
+    same_line_arrays: bool,
+    skip_null: bool,
+    nowdoc: bool,
+    double_quote: bool,
+    ascii_only: bool,
+    max_depth: Option<usize>,
+    max_bytes: Option<usize>,
+    written: usize,
+    buffered: usize,
+    key: Option<Vec<u8>>,
+    key_name: Option<String>,
+    in_key: bool,
+    path: Vec<PathSegment>,
+    nullable_entry: bool,
+    skipped_entry: bool,
+    raw: bool,
+    call_args: bool,
+    spread_args: bool,
+    object: bool,
+    pending_class: bool,
+    closers: Vec<&'static [u8]>,
+    frames: Vec<Frame>,
 }
 
 impl<W: Write> Serializer<W> {
     #[inline]
-    fn new(writer: W) -> Self {
+    pub fn new(writer: W) -> Self {
         Self {
             writer,
             current_ident: 0,
+            line_width: None,
+            align_arrows: false,
+            same_line_arrays: false,
+            skip_null: false,
+            nowdoc: false,
+            double_quote: false,
+            ascii_only: false,
+            max_depth: None,
+            max_bytes: None,
+            written: 0,
+            buffered: 0,
+            key: None,
+            key_name: None,
+            in_key: false,
+            path: Vec::new(),
+            nullable_entry: false,
+            skipped_entry: false,
+            raw: false,
+            call_args: false,
+            spread_args: false,
+            object: false,
+            pending_class: false,
+            closers: Vec::new(),
+            frames: Vec::new(),
         }
     }
 
+    /// Write arrays which fit in `width` columns on a single line, like
+    /// `array(0 => 1, 1 => 2)`, and only break the ones that don't fit.
+    ///
+    /// The arrays are buffered until the outermost one is complete.
+    pub fn line_width(&mut self, width: Option<usize>) -> &mut Self {
+        self.line_width = width;
+        self
+    }
+
+    /// Pad the keys of broken arrays so that the ` => ` of all entries line
+    /// up, like php-cs-fixer's `align_single_space_minimal`.
+    ///
+    /// The arrays are buffered until the outermost one is complete.
+    pub fn align_arrows(&mut self, align: bool) -> &mut Self {
+        self.align_arrows = align;
+        self
+    }
+
+    /// Start nested arrays on the same line as their key, like
+    /// `'names' => array(`, instead of on the next line.
+    pub fn same_line_arrays(&mut self, same_line: bool) -> &mut Self {
+        self.same_line_arrays = same_line;
+        self
+    }
+
+    /// Omit the struct fields and map entries whose value is `NULL`, such as
+    /// `None` or `()`.
+    pub fn skip_null(&mut self, skip: bool) -> &mut Self {
+        self.skip_null = skip;
+        self
+    }
+
+    /// Write the strings containing newlines as flexible nowdocs indented to
+    /// the level of their entry, which requires PHP 7.3 or newer.
+    pub fn nowdoc_strings(&mut self, nowdoc: bool) -> &mut Self {
+        self.nowdoc = nowdoc;
+        self
+    }
+
+    /// Write the strings containing control characters double-quoted, with
+    /// escape sequences like `\n`, `\t` or `\x00` instead of raw characters.
+    ///
+    /// Newlines are left to [`nowdoc_strings`](Self::nowdoc_strings) when both
+    /// are enabled.
+    pub fn escape_control_chars(&mut self, escape: bool) -> &mut Self {
+        self.double_quote = escape;
+        self
+    }
+
+    /// Write the strings containing non-ASCII characters double-quoted, with
+    /// escape sequences like `\u{1F600}`, so that the output is pure ASCII.
+    pub fn ascii_only(&mut self, ascii: bool) -> &mut Self {
+        self.ascii_only = ascii;
+        self
+    }
+
+    /// Fail with [`Error::DepthLimitExceeded`] instead of writing arrays nested
+    /// deeper than `depth`.
+    pub fn max_depth(&mut self, depth: Option<usize>) -> &mut Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Fail with [`Error::SizeLimitExceeded`] as soon as the output would be
+    /// longer than `bytes`.
+    ///
+    /// The output is written as it goes, so the bytes under the limit have
+    /// already been written to the writer when it fails.
+    pub fn max_bytes(&mut self, bytes: Option<usize>) -> &mut Self {
+        self.max_bytes = bytes;
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    #[inline]
+    fn is_buffering(&self) -> bool {
+        self.line_width.is_some() || self.align_arrows || self.call_args
+    }
+
+    fn write_key<T: ?Sized>(&mut self, key: &T) -> Result<()>
+    where
+        T: Serialize,
+    {
+        self.in_key = true;
+        let result = key.serialize(&mut *self);
+        self.in_key = false;
+        result?;
+
+        let segment = match self.key_name.take() {
+            Some(name) => PathSegment::Key(name),
+            None => PathSegment::Index(
+                String::from_utf8_lossy(self.key.as_deref().unwrap_or_default()).into_owned(),
+            ),
+        };
+        self.path.push(segment);
+        Ok(())
+    }
+
+    /// Attach the current path to a custom error which doesn't have one yet.
+    fn with_path(&self, err: Error) -> Error {
+        match err {
+            err if err.is_data() && err.path().is_none() && !self.path.is_empty() => {
+                Error::AtPath {
+                    path: self.path(),
+                    source: Box::new(err),
+                }
+            }
+            err => err,
+        }
+    }
+
+    /// Path of the value being serialized, like `database.connections[2]`.
+    fn path(&self) -> String {
+        let mut path = String::new();
+        for segment in &self.path {
+            match segment {
+                PathSegment::Key(name) => {
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(name);
+                }
+                PathSegment::Index(index) => {
+                    path.push('[');
+                    path.push_str(index);
+                    path.push(']');
+                }
+            }
+        }
+        path
+    }
+
+    fn write_scalar(&mut self, value: &[u8]) -> Result<()> {
+        if self.in_key {
+            self.key = Some(value.to_vec());
+            return Ok(());
+        }
+        self.nullable_entry = false;
+        if !self.frames.is_empty() {
+            let key = self.key.take().unwrap_or_default();
+            // The buffered keys and scalars are a lower bound of the output.
+            self.buffered += key.len() + value.len();
+            if let Some(limit) = self.max_bytes {
+                if self.written + self.buffered > limit {
+                    return Err(Error::SizeLimitExceeded { limit });
+                }
+            }
+            if let Some(frame) = self.frames.last_mut() {
+                frame.entries.push((key, Node::Scalar(value.to_vec())));
+            }
+            return Ok(());
+        }
+        if let Some(key) = self.key.take() {
+            self.write_ident()?;
+            self.write_all(&key)?;
+            self.write_map_symbol()?;
+        }
+        self.write_all(value)?;
+        Ok(())
+    }
+
     fn write_begin_array(&mut self) -> Result<()> {
-        if self.current_ident > 0 {
-            self.writer.write_all(b"\n")?;
+        if self.in_key {
+            return Err(Error::InvalidKey);
+        }
+        self.nullable_entry = false;
+        if let Some(limit) = self.max_depth {
+            let depth = if self.is_buffering() {
+                self.frames.len()
+            } else {
+                self.current_ident
+            };
+            if depth >= limit {
+                return Err(Error::DepthLimitExceeded {
+                    limit,
+                    path: self.path(),
+                });
+            }
+        }
+        // The array of an object is opened once its class is known.
+        self.pending_class = std::mem::take(&mut self.object);
+        if self.is_buffering() {
+            let key = self.key.take();
+            self.frames.push(Frame {
+                key,
+                open: ARRAY_OPEN.to_vec(),
+                entries: Vec::new(),
+                close: ARRAY_CLOSE,
+            });
+            return Ok(());
+        }
+        if let Some(key) = self.key.take() {
             self.write_ident()?;
+            self.write_all(&key)?;
+            if self.same_line_arrays {
+                self.write_map_symbol()?;
+            } else {
+                self.write_map_symbol_break()?;
+            }
+        }
+        if !self.pending_class {
+            self.write_open(ARRAY_OPEN, ARRAY_CLOSE)?;
         }
-        self.writer.write_all(b"array(\n")?;
+        Ok(())
+    }
+
+    /// Open an array in streaming mode.
+    fn write_open(&mut self, open: &[u8], close: &'static [u8]) -> Result<()> {
+        self.write_all(open)?;
+        self.write_all(b"\n")?;
         self.current_ident += 1;
+        self.closers.push(close);
         Ok(())
     }
 
+    /// Use the class written as the first entry of an object to open it.
+    fn write_class(&mut self, class: &str) -> Result<()> {
+        self.pending_class = false;
+        self.nullable_entry = false;
+        self.key = None;
+        self.skipped_entry = true;
+        let (open, close) = object_delimiters(class);
+        match self.frames.last_mut() {
+            Some(frame) => {
+                frame.open = open;
+                frame.close = close;
+                Ok(())
+            }
+            None => self.write_open(&open, close),
+        }
+    }
+
     fn write_end_array(&mut self) -> Result<()> {
+        if let Some(frame) = self.frames.pop() {
+            let node = Node::Array {
+                open: frame.open,
+                entries: frame.entries,
+                close: frame.close,
+            };
+            if let Some(parent) = self.frames.last_mut() {
+                parent.entries.push((frame.key.unwrap_or_default(), node));
+                return Ok(());
+            }
+            self.buffered = 0;
+            if self.spread_args {
+                self.write_call_args(&node)?;
+            } else if self.call_args {
+                self.write_flat(&node)?;
+            } else {
+                self.write_node(&node, 0, 0)?;
+            }
+            return Ok(());
+        }
+        let close = self.closers.pop().unwrap_or(ARRAY_CLOSE);
         self.current_ident -= 1;
         self.write_ident()?;
-        self.writer.write_all(b")")?;
+        self.write_all(close)?;
+        Ok(())
+    }
+
+    fn write_str(&mut self, v: &str) -> Result<()> {
+        if self.in_key {
+            self.key_name = Some(v.to_owned());
+        } else if self.pending_class {
+            return self.write_class(v);
+        }
+        if self.raw {
+            return self.write_scalar(v.as_bytes());
+        }
+        let is_nowdoc = self.nowdoc && !self.in_key && v.contains('\n');
+        let escape = v.chars().any(|c| {
+            (self.double_quote && c.is_ascii_control() && !(is_nowdoc && c == '\n'))
+                || (self.ascii_only && !c.is_ascii())
+        });
+        if escape {
+            return self.write_scalar(double_quoted(v, self.ascii_only).as_bytes());
+        }
+        if is_nowdoc {
+            let indent = if self.frames.is_empty() {
+                self.current_ident
+            } else {
+                self.frames.len()
+            };
+            return self.write_scalar(&nowdoc(v, indent));
+        }
+
+        let mut s = String::new();
+        s.push('\'');
+        for c in v.chars() {
+            if c == '\'' || c == '\\' {
+                s.push('\\');
+            }
+            s.push(c);
+        }
+        s.push('\'');
+        self.write_scalar(s.as_bytes())
+    }
+
+    fn write_null(&mut self) -> Result<()> {
+        if self.nullable_entry && !self.in_key {
+            self.key = None;
+            self.nullable_entry = false;
+            self.skipped_entry = true;
+            return Ok(());
+        }
+        self.write_scalar(b"NULL")
+    }
+
+    fn write_end_entry(&mut self) -> Result<()> {
+        self.path.pop();
+        if self.skipped_entry {
+            self.skipped_entry = false;
+            return Ok(());
+        }
+        if self.frames.is_empty() {
+            self.write_all(b",\n")?;
+        }
+        Ok(())
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+        self.written += buf.len();
+        match self.max_bytes {
+            Some(limit) if self.written > limit => {
+                return Err(Error::SizeLimitExceeded { limit });
+            }
+            _ => {}
+        }
+        self.writer.write_all(buf)?;
         Ok(())
     }
 
     #[inline]
     fn write_ident(&mut self) -> Result<()> {
         for _ in 0..self.current_ident {
-            self.writer.write_all(INDENT)?;
+            self.write_all(INDENT)?;
         }
         Ok(())
     }
 
     fn write_map_symbol(&mut self) -> Result<()> {
-        self.writer.write_all(b" => ")?;
+        self.write_all(b" => ")?;
+        Ok(())
+    }
+
+    /// Write the ` =>` of an entry whose value starts on the next line.
+    fn write_map_symbol_break(&mut self) -> Result<()> {
+        self.write_all(b" =>\n")?;
+        self.write_ident()?;
+        Ok(())
+    }
+
+    /// Write a buffered node starting at `column`, followed by `trailing`
+    /// columns of punctuation.
+    fn write_node(&mut self, node: &Node, column: usize, trailing: usize) -> Result<()> {
+        let (open, entries, close) = match node {
+            Node::Scalar(value) => {
+                self.write_all(value)?;
+                return Ok(());
+            }
+            Node::Array {
+                open,
+                entries,
+                close,
+            } => (open, entries, *close),
+        };
+        if self.fits(node, column + trailing) {
+            return self.write_flat(node);
+        }
+
+        let key_width = if self.align_arrows {
+            entries.iter().map(|(key, _)| text_width(key)).max()
+        } else {
+            None
+        };
+
+        self.write_all(open)?;
+        self.write_all(b"\n")?;
+        self.current_ident += 1;
+        for (key, value) in entries {
+            self.write_ident()?;
+            self.write_all(key)?;
+            let key_width = key_width.unwrap_or_else(|| text_width(key));
+            for _ in text_width(key)..key_width {
+                self.write_all(b" ")?;
+            }
+            let mut column = self.current_ident * INDENT.len() + key_width + 4;
+            match value {
+                Node::Array { .. } if !self.same_line_arrays && !self.fits(value, column + 1) => {
+                    self.write_map_symbol_break()?;
+                    column = self.current_ident * INDENT.len();
+                }
+                _ => self.write_map_symbol()?,
+            }
+            self.write_node(value, column, 1)?;
+            self.write_all(b",\n")?;
+        }
+        self.current_ident -= 1;
+        self.write_ident()?;
+        self.write_all(close)?;
         Ok(())
     }
+
+    fn write_flat(&mut self, node: &Node) -> Result<()> {
+        match node {
+            Node::Scalar(value) => self.write_all(value)?,
+            Node::Array {
+                open,
+                entries,
+                close,
+            } => {
+                self.write_all(open)?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        self.write_all(b", ")?;
+                    }
+                    self.write_all(key)?;
+                    self.write_map_symbol()?;
+                    self.write_flat(value)?;
+                }
+                self.write_all(close)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_call_args(&mut self, node: &Node) -> Result<()> {
+        if let Node::Array { entries, .. } = node {
+            for (i, (_, value)) in entries.iter().enumerate() {
+                if i > 0 {
+                    self.write_all(b", ")?;
+                }
+                self.write_flat(value)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn fits(&self, node: &Node, used: usize) -> bool {
+        match self.line_width {
+            Some(width) if used <= width => flat_width(node, width - used).is_some(),
+            _ => false,
+        }
+    }
+}
+
+/// Width of `node` written on a single line, or `None` if it is wider than
+/// `max` or can't be written on a single line.
+fn flat_width(node: &Node, max: usize) -> Option<usize> {
+    let width = match node {
+        Node::Scalar(value) if value.contains(&b'\n') => return None,
+        Node::Scalar(value) => text_width(value),
+        Node::Array {
+            open,
+            entries,
+            close,
+        } => {
+            let mut width =
+                text_width(open) + close.len() + entries.len().saturating_sub(1) * ", ".len();
+            for (key, value) in entries {
+                width += text_width(key) + " => ".len();
+                if width > max {
+                    return None;
+                }
+                width += flat_width(value, max - width)?;
+            }
+            width
+        }
+    };
+    if width <= max {
+        Some(width)
+    } else {
+        None
+    }
+}
+
+/// Double-quoted literal of `v` with the control characters, and the
+/// non-ASCII ones if `ascii` is set, escaped.
+fn double_quoted(v: &str, ascii: bool) -> String {
+    let mut s = String::with_capacity(v.len() + 2);
+    s.push('"');
+    for c in v.chars() {
+        match c {
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\t' => s.push_str("\\t"),
+            '\x0b' => s.push_str("\\v"),
+            '\x0c' => s.push_str("\\f"),
+            '\x1b' => s.push_str("\\e"),
+            '\\' => s.push_str("\\\\"),
+            '"' => s.push_str("\\\""),
+            '$' => s.push_str("\\$"),
+            c if c.is_ascii_control() => s.push_str(&format!("\\x{:02X}", c as u32)),
+            c if ascii && !c.is_ascii() => s.push_str(&format!("\\u{{{:X}}}", c as u32)),
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+    s
+}
+
+/// Flexible nowdoc of `v` whose body and closing identifier are indented by
+/// `indent` levels.
+fn nowdoc(v: &str, indent: usize) -> Vec<u8> {
+    let mut label = "EOT".to_owned();
+    let mut n = 0;
+    while v.contains(&label) {
+        n += 1;
+        label = format!("EOT{}", n);
+    }
+
+    let mut doc = format!("<<<'{}'\n", label).into_bytes();
+    for line in v.split('\n') {
+        if !line.is_empty() {
+            doc.extend(INDENT.repeat(indent));
+            doc.extend(line.as_bytes());
+        }
+        doc.push(b'\n');
+    }
+    doc.extend(INDENT.repeat(indent));
+    doc.extend(label.as_bytes());
+    doc
+}
+
+/// Constant of a non-finite float, written like `var_export()` does.
+fn non_finite(v: f64) -> Option<&'static [u8]> {
+    if v.is_nan() {
+        Some(b"NAN")
+    } else if v.is_infinite() {
+        Some(if v > 0.0 { b"INF" } else { b"-INF" })
+    } else {
+        None
+    }
+}
+
+/// Number of characters in the UTF-8 encoded `text`.
+#[inline]
+fn text_width(text: &[u8]) -> usize {
+    text.iter().filter(|&&b| b & 0xC0 != 0x80).count()
 }
 
 impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
@@ -108,58 +726,71 @@ impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
     type SerializeStructVariant = Self;
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
-        self.writer.write_all(if v { b"true" } else { b"false" })?;
-        Ok(())
+        self.write_scalar(if v { b"true" } else { b"false" })
     }
 
     fn serialize_i8(self, v: i8) -> Result<Self::Ok> {
-        itoa::write(&mut self.writer, v)?;
-        Ok(())
+        self.write_scalar(itoa::Buffer::new().format(v).as_bytes())
     }
 
     fn serialize_i16(self, v: i16) -> Result<Self::Ok> {
-        itoa::write(&mut self.writer, v)?;
-        Ok(())
+        self.write_scalar(itoa::Buffer::new().format(v).as_bytes())
     }
 
     fn serialize_i32(self, v: i32) -> Result<Self::Ok> {
-        itoa::write(&mut self.writer, v)?;
-        Ok(())
+        self.write_scalar(itoa::Buffer::new().format(v).as_bytes())
     }
 
     fn serialize_i64(self, v: i64) -> Result<Self::Ok> {
-        itoa::write(&mut self.writer, v)?;
-        Ok(())
+        self.write_scalar(itoa::Buffer::new().format(v).as_bytes())
     }
 
     fn serialize_u8(self, v: u8) -> Result<Self::Ok> {
-        itoa::write(&mut self.writer, v)?;
-        Ok(())
+        self.write_scalar(itoa::Buffer::new().format(v).as_bytes())
     }
 
     fn serialize_u16(self, v: u16) -> Result<Self::Ok> {
-        itoa::write(&mut self.writer, v)?;
-        Ok(())
+        self.write_scalar(itoa::Buffer::new().format(v).as_bytes())
     }
 
     fn serialize_u32(self, v: u32) -> Result<Self::Ok> {
-        itoa::write(&mut self.writer, v)?;
-        Ok(())
+        self.write_scalar(itoa::Buffer::new().format(v).as_bytes())
     }
 
     fn serialize_u64(self, v: u64) -> Result<Self::Ok> {
-        itoa::write(&mut self.writer, v)?;
-        Ok(())
+        self.write_scalar(itoa::Buffer::new().format(v).as_bytes())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok> {
+        if v < i64::MIN as i128 || v > i64::MAX as i128 {
+            return Err(Error::IntegerOverflow(v.to_string()));
+        }
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok> {
+        if v > i64::MAX as u128 {
+            return Err(Error::IntegerOverflow(v.to_string()));
+        }
+        self.serialize_i64(v as i64)
     }
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok> {
-        dtoa::write(&mut self.writer, v)?;
-        Ok(())
+        if let Some(name) = non_finite(v.into()) {
+            return self.write_scalar(name);
+        }
+        let mut buf = Vec::new();
+        dtoa::write(&mut buf, v)?;
+        self.write_scalar(&buf)
     }
 
     fn serialize_f64(self, v: f64) -> Result<Self::Ok> {
-        dtoa::write(&mut self.writer, v)?;
-        Ok(())
+        if let Some(name) = non_finite(v) {
+            return self.write_scalar(name);
+        }
+        let mut buf = Vec::new();
+        dtoa::write(&mut buf, v)?;
+        self.write_scalar(&buf)
     }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok> {
@@ -169,17 +800,7 @@ impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok> {
-        let mut s = String::new();
-        s.push('\'');
-        for c in v.chars() {
-            if c == '\'' || c == '\\' {
-                s.push('\\');
-            }
-            s.push(c);
-        }
-        s.push('\'');
-        self.writer.write_all(s.as_bytes())?;
-        Ok(())
+        self.write_str(v)
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
@@ -192,8 +813,7 @@ impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
     }
 
     fn serialize_none(self) -> Result<Self::Ok> {
-        self.writer.write_all(b"NULL")?;
-        Ok(())
+        self.write_null()
     }
 
     fn serialize_some<T: ?Sized>(self, value: &T) -> Result<Self::Ok>
@@ -204,13 +824,14 @@ impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
     }
 
     fn serialize_unit(self) -> Result<Self::Ok> {
-        self.writer.write_all(b"NULL")?;
-        Ok(())
+        if self.call_args && self.frames.is_empty() {
+            return Ok(());
+        }
+        self.write_null()
     }
 
     fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
-        self.writer.write_all(b"NULL")?;
-        Ok(())
+        self.write_null()
     }
 
     fn serialize_unit_variant(
@@ -223,10 +844,19 @@ impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
         Ok(())
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(self, _name: &'static str, value: &T) -> Result<Self::Ok>
+    fn serialize_newtype_struct<T: ?Sized>(self, name: &'static str, value: &T) -> Result<Self::Ok>
     where
         T: Serialize,
     {
+        if name == raw::TOKEN || name == value::BYTES_TOKEN || name == value::ENUM_TOKEN {
+            self.raw = true;
+            let result = value.serialize(&mut *self);
+            self.raw = false;
+            return result;
+        }
+        if name == value::OBJECT_TOKEN {
+            self.object = true;
+        }
         value.serialize(self)
     }
 
@@ -241,16 +871,20 @@ impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
         T: Serialize,
     {
         self.write_begin_array()?;
-        self.write_ident()?;
-        variant.serialize(&mut *self)?;
-        self.write_map_symbol()?;
-        value.serialize(&mut *self)?;
-        self.writer.write_all(b",\n")?;
+        self.write_key(variant)?;
+        value
+            .serialize(&mut *self)
+            .map_err(|err| self.with_path(err))?;
+        self.write_end_entry()?;
         self.write_end_array()?;
         Ok(())
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        if self.frames.is_empty() {
+            // Only a top level sequence or tuple is spread into arguments.
+            self.spread_args = self.call_args;
+        }
         self.write_begin_array()?;
         Ok(SeqSerializer::new(self))
     }
@@ -275,9 +909,7 @@ impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
         self.write_begin_array()?;
-        self.write_ident()?;
-        variant.serialize(&mut *self)?;
-        self.writer.write_all(b" => ")?;
+        self.write_key(variant)?;
         self.write_begin_array()?;
         Ok(SeqSerializer::new(self))
     }
@@ -299,9 +931,7 @@ impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
         _len: usize,
     ) -> Result<Self::SerializeStructVariant> {
         self.write_begin_array()?;
-        self.write_ident()?;
-        variant.serialize(&mut *self)?;
-        self.writer.write_all(b" => ")?;
+        self.write_key(variant)?;
         self.write_begin_array()?;
         Ok(self)
     }
@@ -315,11 +945,11 @@ impl<'a, W: Write> ser::SerializeSeq for SeqSerializer<'a, W> {
     where
         T: Serialize,
     {
-        self.ser.write_ident()?;
-        (self.idx as u64).serialize(&mut *self.ser)?;
-        self.ser.write_map_symbol()?;
-        value.serialize(&mut *self.ser)?;
-        self.as_mut_writer().write_all(b",\n")?;
+        self.ser.write_key(&(self.idx as u64))?;
+        value
+            .serialize(&mut *self.ser)
+            .map_err(|err| self.ser.with_path(err))?;
+        self.ser.write_end_entry()?;
         self.idx += 1;
         Ok(())
     }
@@ -373,9 +1003,9 @@ impl<'a, W: Write> ser::SerializeTupleVariant for SeqSerializer<'a, W> {
         ser::SerializeSeq::serialize_element(self, value)
     }
 
-    fn end(mut self) -> Result<Self::Ok> {
+    fn end(self) -> Result<Self::Ok> {
         self.ser.write_end_array()?;
-        self.as_mut_writer().write_all(b",\n")?;
+        self.ser.write_end_entry()?;
         self.ser.write_end_array()?;
         Ok(())
     }
@@ -389,18 +1019,18 @@ impl<'a, W: Write> ser::SerializeMap for &'a mut Serializer<W> {
     where
         T: Serialize,
     {
-        self.write_ident()?;
-        key.serialize(&mut **self)?;
-        Ok(())
+        self.write_key(key)
     }
 
     fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<()>
     where
         T: Serialize,
     {
-        self.write_map_symbol()?;
-        value.serialize(&mut **self)?;
-        self.writer.write_all(b",\n")?;
+        self.nullable_entry = self.skip_null;
+        value
+            .serialize(&mut **self)
+            .map_err(|err| self.with_path(err))?;
+        self.write_end_entry()?;
         Ok(())
     }
 
@@ -441,7 +1071,7 @@ impl<'a, W: Write> ser::SerializeStructVariant for &'a mut Serializer<W> {
 
     fn end(self) -> Result<Self::Ok> {
         self.write_end_array()?;
-        self.writer.write_all(b",\n")?;
+        self.write_end_entry()?;
         self.write_end_array()?;
         Ok(())
     }
@@ -801,7 +1431,7 @@ mod serializer_tests {
 
         assert_eq!(
             std::str::from_utf8(tuple_variant_serializer.as_mut_writer()).unwrap(),
-            "array(\n  'Bar' => \n  array(\n"
+            "array(\n  'Bar' =>\n  array(\n"
         );
         assert_eq!(tuple_variant_serializer.idx, 0);
 
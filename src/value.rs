@@ -0,0 +1,277 @@
+use serde::de::{Deserialize, Deserializer, Error, MapAccess, SeqAccess, Unexpected, Visitor};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::array::{ArrayKey, ArrayVisitor, PhpArray};
+use crate::raw::RawPhp;
+use std::fmt;
+
+mod de;
+mod index;
+mod ser;
+
+pub use self::de::from_value;
+pub use self::ser::to_value;
+
+/// Newtype struct name recognized by the deserializer to give a [`Value`] the
+/// arrays with their keys, the objects with their class, the enum cases and the
+/// raw expressions.
+pub(crate) const TOKEN: &str = "$serde_var_export::private::Value";
+
+/// Newtype struct name recognized by the serializer to write a map whose first
+/// entry is the class under [`CLASS_KEY`] as an object.
+pub(crate) const OBJECT_TOKEN: &str = "$serde_var_export::private::Object";
+
+/// Newtype struct name recognized by the serializers around the double-quoted
+/// PHP string of bytes which aren't UTF-8, written verbatim like a [`RawPhp`].
+pub(crate) const BYTES_TOKEN: &str = "$serde_var_export::private::Bytes";
+
+/// Newtype struct name recognized by the serializers around an enum case like
+/// `\Suit::Hearts`, written verbatim like a [`RawPhp`].
+pub(crate) const ENUM_TOKEN: &str = "$serde_var_export::private::Enum";
+
+/// Key of the class of an object given to [`Value`] as a map.
+pub(crate) const CLASS_KEY: &str = "$serde_var_export::private::class";
+
+/// Key of an enum case given to [`Value`] as a map, like `Suit::Hearts`.
+pub(crate) const ENUM_CASE_KEY: &str = "$serde_var_export::private::enum_case";
+
+/// Key of the expression of a [`Value::Raw`] given back to [`Value`] as a map.
+pub(crate) const RAW_KEY: &str = "$serde_var_export::private::raw";
+
+/// Any PHP value.
+///
+/// ```rust
+/// use serde_var_export::Value;
+///
+/// let value: Value = serde_var_export::from_str(
+///     r"array(
+///   'driver' => 'mysql',
+///   'options' => \PDO::__set_state(array(
+///   )),
+/// )",
+/// )
+/// .unwrap();
+/// match value {
+///     Value::Array(config) => assert_eq!(config.len(), 2),
+///     _ => unreachable!(),
+/// }
+/// ```
+///
+/// Other formats see an object as a map of its properties with its class under
+/// a private key, an enum case as the string `\Class::Case` and a string or key
+/// which isn't UTF-8 as a double-quoted PHP string.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    /// Byte string, which is UTF-8 most of the time but doesn't have to be.
+    String(Vec<u8>),
+    Array(PhpArray),
+    /// Instance of `class`, written with `__set_state()`, or `(object)` for
+    /// `stdClass`.
+    Object {
+        class: String,
+        properties: PhpArray,
+    },
+    /// Case of a PHP 8.1 enum, like `\Suit::Hearts`.
+    Enum {
+        class: String,
+        case: String,
+    },
+    /// Expression written verbatim, like a call of `env()`, from a [`RawPhp`]
+    /// given to [`to_value`].
+    Raw(RawPhp),
+}
+
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Int(n) => serializer.serialize_i64(*n),
+            Value::Float(n) => serializer.serialize_f64(*n),
+            Value::String(s) => match std::str::from_utf8(s) {
+                Ok(s) => serializer.serialize_str(s),
+                Err(_) => serializer.serialize_newtype_struct(BYTES_TOKEN, &byte_string(s)),
+            },
+            Value::Array(array) => array.serialize(serializer),
+            Value::Object { class, properties } => {
+                serializer.serialize_newtype_struct(OBJECT_TOKEN, &Object { class, properties })
+            }
+            Value::Enum { class, case } => {
+                let name = RawPhp::class_constant(class, case).into_string();
+                serializer.serialize_newtype_struct(ENUM_TOKEN, &name)
+            }
+            Value::Raw(raw) => raw.serialize(serializer),
+        }
+    }
+}
+
+/// Double-quoted PHP string of bytes which aren't UTF-8.
+pub(crate) fn byte_string(v: &[u8]) -> String {
+    let mut s = String::with_capacity(v.len() + 2);
+    s.push('"');
+    for &b in v {
+        match b {
+            b'\\' => s.push_str("\\\\"),
+            b'"' => s.push_str("\\\""),
+            b'$' => s.push_str("\\$"),
+            b' '..=b'~' => s.push(b as char),
+            _ => s.push_str(&format!("\\x{:02X}", b)),
+        }
+    }
+    s.push('"');
+    s
+}
+
+/// Enum case named like `\Suit::Hearts` or `Suit::Hearts`.
+pub(crate) fn enum_case(name: &str) -> Option<Value> {
+    let i = name.rfind("::")?;
+    Some(Value::Enum {
+        class: name[..i].trim_start_matches('\\').to_owned(),
+        case: name[i + 2..].to_owned(),
+    })
+}
+
+/// Object serialized as a map whose first entry is its class.
+struct Object<'a> {
+    class: &'a str,
+    properties: &'a PhpArray,
+}
+
+impl Serialize for Object<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.properties.len() + 1))?;
+        map.serialize_entry(CLASS_KEY, self.class)?;
+        for (key, value) in self.properties {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(TOKEN, ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any PHP value")
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Int(v))
+    }
+
+    /// Integers overflowing PHP int become floats, like in PHP.
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        if v > i64::MAX as u64 {
+            return Ok(Value::Float(v as f64));
+        }
+        Ok(Value::Int(v as i64))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.as_bytes().to_vec()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v.into_bytes()))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Value, E> {
+        Ok(Value::String(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        ArrayVisitor.visit_seq(seq).map(Value::Array)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let key = match map.next_key::<ArrayKey>()? {
+            Some(key) => key,
+            None => return Ok(Value::Array(PhpArray::new())),
+        };
+        match &key {
+            ArrayKey::String(s) if s == CLASS_KEY.as_bytes() => {
+                let class = map.next_value()?;
+                let properties = ArrayVisitor::visit_entries(PhpArray::new(), map)?;
+                Ok(Value::Object { class, properties })
+            }
+            ArrayKey::String(s) if s == ENUM_CASE_KEY.as_bytes() => {
+                let name: String = map.next_value()?;
+                enum_case(&name).ok_or_else(|| {
+                    Error::invalid_value(
+                        Unexpected::Str(&name),
+                        &"an enum case like `Suit::Hearts`",
+                    )
+                })
+            }
+            ArrayKey::String(s) if s == RAW_KEY.as_bytes() => {
+                Ok(Value::Raw(RawPhp::new(map.next_value::<String>()?)))
+            }
+            _ => {
+                let mut array = PhpArray::new();
+                array.insert(key, map.next_value()?);
+                ArrayVisitor::visit_entries(array, map).map(Value::Array)
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use serde::de::DeserializeOwned;
+
+use super::Value;
+use crate::array::{ArrayKey, PhpArray};
+use crate::de::Deserializer;
+use crate::error::Result;
+use crate::parse::{Expr, ExprKind, Key};
+use std::borrow::Cow;
+
+/// Convert a [`Value`] to `T`, reading arrays, objects and enum cases like
+/// [`from_str`](crate::from_str) does.
+///
+/// ```rust
+/// use serde_derive::Deserialize;
+/// use serde_var_export::Value;
+///
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// struct Config {
+///     debug: bool,
+///     hosts: Vec<String>,
+/// }
+///
+/// let mut value: Value = serde_var_export::from_str(
+///     "array('debug' => false, 'hosts' => array('a', 'b'))",
+/// )
+/// .unwrap();
+/// if let Value::Array(config) = &mut value {
+///     config.insert("debug", Value::Bool(true));
+/// }
+/// let config: Config = serde_var_export::from_value(value).unwrap();
+/// assert!(config.debug);
+/// ```
+///
+/// [`Deserializer::from_value`] takes the same options as when reading PHP.
+pub fn from_value<T>(value: Value) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    T::deserialize(&mut Deserializer::from_value(&value))
+}
+
+impl Value {
+    /// Value as if it was parsed, to be deserialized like the input.
+    pub(crate) fn as_expr(&self) -> Expr<'_> {
+        let kind = match self {
+            Value::Null => ExprKind::Null,
+            Value::Bool(b) => ExprKind::Bool(*b),
+            Value::Int(n) => ExprKind::Int(*n),
+            Value::Float(n) => ExprKind::Float(*n),
+            Value::String(s) => ExprKind::Str(Cow::Borrowed(s)),
+            Value::Array(array) => ExprKind::Array(entries(array)),
+            Value::Object { class, properties } => ExprKind::Object {
+                class,
+                properties: entries(properties),
+            },
+            Value::Enum { class, case } => ExprKind::EnumCase { class, case },
+            Value::Raw(raw) => ExprKind::Raw(raw.as_str()),
+        };
+        Expr { kind, offset: 0 }
+    }
+}
+
+fn entries(array: &PhpArray) -> Vec<(Key<'_>, Expr<'_>)> {
+    array
+        .iter()
+        .map(|(key, value)| {
+            let key = match key {
+                ArrayKey::Int(n) => Key::Int(*n),
+                ArrayKey::String(s) => Key::Str(Cow::Borrowed(s)),
+            };
+            (key, value.as_expr())
+        })
+        .collect()
+}
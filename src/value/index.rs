@@ -0,0 +1,141 @@
+use super::Value;
+use crate::array::{ArrayKey, PhpArray};
+use std::ops;
+
+static NULL: Value = Value::Null;
+
+impl Value {
+    /// Entries of an array or properties of an object.
+    fn entries(&self) -> Option<&PhpArray> {
+        match self {
+            Value::Array(array)
+            | Value::Object {
+                properties: array, ..
+            } => Some(array),
+            _ => None,
+        }
+    }
+
+    fn entries_mut(&mut self) -> Option<&mut PhpArray> {
+        match self {
+            Value::Array(array)
+            | Value::Object {
+                properties: array, ..
+            } => Some(array),
+            _ => None,
+        }
+    }
+
+    /// Look up a value by a path of keys separated by dots, with integer keys
+    /// also allowed in brackets like in the paths of errors:
+    /// `database.connections.mysql.host` or `hosts[0]`.
+    ///
+    /// ```rust
+    /// use serde_var_export::{php, Value};
+    ///
+    /// let config = php!(["database" => ["hosts" => ["a", "b"]]]);
+    /// assert_eq!(
+    ///     config.pointer("database.hosts[1]"),
+    ///     Some(&Value::String(b"b".to_vec()))
+    /// );
+    /// assert_eq!(config.pointer("database.hosts.1"), config.pointer("database.hosts[1]"));
+    /// assert_eq!(config.pointer("database.port"), None);
+    /// ```
+    ///
+    /// The empty path is the value itself.
+    pub fn pointer(&self, path: &str) -> Option<&Value> {
+        path_keys(path)
+            .into_iter()
+            .try_fold(self, |value, key| value.entries()?.get(key))
+    }
+
+    /// Look up a value like [`pointer`](Value::pointer) for modification.
+    /// Unlike indexing, missing keys are not created.
+    pub fn pointer_mut(&mut self, path: &str) -> Option<&mut Value> {
+        path_keys(path)
+            .into_iter()
+            .try_fold(self, |value, key| value.entries_mut()?.get_mut(key))
+    }
+
+    /// Remove the value at `path`, see [`pointer`](Value::pointer), from its
+    /// array or object and return it.
+    pub fn remove_path(&mut self, path: &str) -> Option<Value> {
+        let mut keys = path_keys(path);
+        let last = keys.pop()?;
+        keys.into_iter()
+            .try_fold(self, |value, key| value.entries_mut()?.get_mut(key))?
+            .entries_mut()?
+            .remove(last)
+    }
+}
+
+/// Keys of a path like `database.connections[2].host`.
+fn path_keys(path: &str) -> Vec<ArrayKey> {
+    let mut keys = Vec::new();
+    if path.is_empty() {
+        return keys;
+    }
+    for segment in path.split('.') {
+        let (name, indexes) = segment.split_at(segment.find('[').unwrap_or(segment.len()));
+        if !name.is_empty() || indexes.is_empty() {
+            keys.push(ArrayKey::from(name));
+        }
+        for index in indexes.split('[').skip(1) {
+            keys.push(ArrayKey::from(index.strip_suffix(']').unwrap_or(index)));
+        }
+    }
+    keys
+}
+
+/// Value of an array entry or object property, `NULL` if there is none, like
+/// PHP without the warning.
+impl<K> ops::Index<K> for Value
+where
+    K: Into<ArrayKey>,
+{
+    type Output = Value;
+
+    fn index(&self, key: K) -> &Value {
+        self.entries()
+            .and_then(|entries| entries.get(key))
+            .unwrap_or(&NULL)
+    }
+}
+
+/// Value of an array entry or object property, inserting `NULL` if there is
+/// none. Like in PHP, `NULL` becomes an empty array first.
+///
+/// ```rust
+/// use serde_var_export::{php, Value};
+///
+/// let mut config = Value::Null;
+/// config["database"]["port"] = php!(3306);
+/// assert_eq!(config, php!(["database" => ["port" => 3306]]));
+/// ```
+///
+/// # Panics
+///
+/// Indexing a value which is neither `NULL`, an array nor an object panics,
+/// where PHP fails with "Cannot use a scalar value as an array".
+impl<K> ops::IndexMut<K> for Value
+where
+    K: Into<ArrayKey>,
+{
+    fn index_mut(&mut self, key: K) -> &mut Value {
+        if let Value::Null = self {
+            *self = Value::Array(PhpArray::new());
+        }
+        let entries = match self {
+            Value::Array(array)
+            | Value::Object {
+                properties: array, ..
+            } => array,
+            value => panic!("cannot index into a scalar value {:?}", value),
+        };
+        let key = key.into();
+        if !entries.contains_key(&key) {
+            entries.insert(key.clone(), Value::Null);
+        }
+        entries.get_mut(key).unwrap()
+    }
+}
@@ -0,0 +1,412 @@
+use serde::{ser, Serialize};
+
+use super::{Value, BYTES_TOKEN, CLASS_KEY, ENUM_TOKEN, OBJECT_TOKEN};
+use crate::array::{ArrayKey, PhpArray};
+use crate::error::{Error, Result};
+use crate::raw::{self, RawPhp};
+
+/// Convert `value` to a [`Value`], with the same arrays, objects and errors as
+/// [`to_string`](crate::to_string).
+///
+/// ```rust
+/// use serde_derive::Serialize;
+/// use serde_var_export::Value;
+///
+/// #[derive(Serialize)]
+/// struct Config {
+///     debug: bool,
+///     hosts: Vec<&'static str>,
+/// }
+///
+/// let mut value = serde_var_export::to_value(&Config {
+///     debug: false,
+///     hosts: vec!["a"],
+/// })
+/// .unwrap();
+/// if let Value::Array(config) = &mut value {
+///     config.insert("debug", Value::Bool(true));
+/// }
+/// assert_eq!(
+///     serde_var_export::to_string(&value).unwrap(),
+///     "array(
+///   'debug' => true,
+///   'hosts' =>
+///   array(
+///     0 => 'a',
+///   ),
+/// )"
+/// );
+/// ```
+pub fn to_value<T>(value: &T) -> Result<Value>
+where
+    T: ?Sized + Serialize,
+{
+    value.serialize(Serializer)
+}
+
+struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = Value;
+    type Error = Error;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = VariantSerializer<SeqSerializer>;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = VariantSerializer<MapSerializer>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value> {
+        Ok(Value::Int(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value> {
+        Ok(Value::Int(v.into()))
+    }
+
+    /// Integers overflowing PHP int become floats, as PHP reads them.
+    fn serialize_u64(self, v: u64) -> Result<Value> {
+        if v > i64::MAX as u64 {
+            return Ok(Value::Float(v as f64));
+        }
+        Ok(Value::Int(v as i64))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value> {
+        if v < i64::MIN as i128 || v > i64::MAX as i128 {
+            return Err(Error::IntegerOverflow(v.to_string()));
+        }
+        Ok(Value::Int(v as i64))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value> {
+        if v > i64::MAX as u128 {
+            return Err(Error::IntegerOverflow(v.to_string()));
+        }
+        Ok(Value::Int(v as i64))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value> {
+        Ok(Value::Float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value> {
+        Ok(Value::String(v.to_string().into_bytes()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value> {
+        Ok(Value::String(v.as_bytes().to_vec()))
+    }
+
+    /// Bytes are a list of integers, like with [`to_string`](crate::to_string).
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value> {
+        let array = v.iter().map(|&b| Value::Int(b.into())).collect();
+        Ok(Value::Array(array))
+    }
+
+    fn serialize_none(self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Value>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value> {
+        self.serialize_str(variant)
+    }
+
+    /// A [`RawPhp`] expression is kept as [`Value::Raw`], while the enum cases
+    /// and the strings of bytes which aren't UTF-8 written by [`Value`] are
+    /// decoded back.
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Value>
+    where
+        T: ?Sized + Serialize,
+    {
+        match (name, value.serialize(self)?) {
+            (raw::TOKEN, Value::String(expr)) => {
+                Ok(Value::Raw(RawPhp::new(String::from_utf8_lossy(&expr))))
+            }
+            (BYTES_TOKEN, Value::String(literal)) => crate::from_slice(&literal),
+            (ENUM_TOKEN, Value::String(name)) => super::enum_case(&String::from_utf8_lossy(&name))
+                .ok_or_else(|| ser::Error::custom("enum case without class")),
+            (OBJECT_TOKEN, Value::Array(mut properties)) => match properties.remove(CLASS_KEY) {
+                Some(Value::String(class)) => Ok(Value::Object {
+                    class: String::from_utf8_lossy(&class).into_owned(),
+                    properties,
+                }),
+                _ => Err(ser::Error::custom("object without class")),
+            },
+            (_, value) => Ok(value),
+        }
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut array = PhpArray::new();
+        array.insert(variant, value.serialize(self)?);
+        Ok(Value::Array(array))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<SeqSerializer> {
+        Ok(SeqSerializer {
+            array: PhpArray::new(),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SeqSerializer> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<VariantSerializer<SeqSerializer>> {
+        Ok(VariantSerializer {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer> {
+        Ok(MapSerializer {
+            array: PhpArray::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapSerializer> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<VariantSerializer<MapSerializer>> {
+        Ok(VariantSerializer {
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+struct SeqSerializer {
+    array: PhpArray,
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.array.push(value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Array(self.array))
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+struct MapSerializer {
+    array: PhpArray,
+    key: Option<ArrayKey>,
+}
+
+/// Key of an array like PHP casts it: booleans become integers and `null` the
+/// empty string.
+fn array_key(key: Value) -> Result<ArrayKey> {
+    match key {
+        Value::Int(n) => Ok(ArrayKey::Int(n)),
+        Value::String(s) => Ok(ArrayKey::from(s)),
+        Value::Bool(b) => Ok(ArrayKey::Int(b.into())),
+        Value::Null => Ok(ArrayKey::from("")),
+        _ => Err(Error::InvalidKey),
+    }
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.key = Some(array_key(key.serialize(Serializer)?)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = self.key.take().ok_or_else(|| {
+            Error::Message("serialize_value called before serialize_key".to_owned())
+        })?;
+        self.array.insert(key, value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Array(self.array))
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeMap::serialize_entry(self, key, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+/// Tuple or struct variant, an array with the variant as only key.
+struct VariantSerializer<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+fn variant_array(variant: &'static str, value: PhpArray) -> Value {
+    let mut array = PhpArray::new();
+    array.insert(variant, Value::Array(value));
+    Value::Array(array)
+}
+
+impl ser::SerializeTupleVariant for VariantSerializer<SeqSerializer> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(variant_array(self.variant, self.inner.array))
+    }
+}
+
+impl ser::SerializeStructVariant for VariantSerializer<MapSerializer> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeMap::serialize_entry(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(variant_array(self.variant, self.inner.array))
+    }
+}
@@ -0,0 +1,693 @@
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+#[test]
+fn deserialize_scalars() {
+    assert!(serde_var_export::from_str::<bool>("true").unwrap());
+    assert_eq!(serde_var_export::from_str::<i32>("-12").unwrap(), -12);
+    assert_eq!(
+        serde_var_export::from_str::<f64>("1.0E+25").unwrap(),
+        1.0e25
+    );
+    assert_eq!(serde_var_export::from_str::<f64>("3").unwrap(), 3.0);
+    assert!(serde_var_export::from_str::<f64>("NAN").unwrap().is_nan());
+    assert_eq!(
+        serde_var_export::from_str::<f64>("-INF").unwrap(),
+        f64::NEG_INFINITY
+    );
+    assert_eq!(
+        serde_var_export::from_str::<String>(r"'it\'s \\ \n'").unwrap(),
+        r"it's \ \n"
+    );
+    assert_eq!(
+        serde_var_export::from_str::<&str>("'borrowed'").unwrap(),
+        "borrowed"
+    );
+    assert_eq!(
+        serde_var_export::from_str::<Option<i32>>("NULL").unwrap(),
+        None
+    );
+    assert_eq!(
+        serde_var_export::from_str::<Option<i32>>(" 1 ").unwrap(),
+        Some(1)
+    );
+    assert_eq!(serde_var_export::from_str::<()>("NULL").unwrap(), ());
+}
+
+#[test]
+fn deserialize_php_var_export() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        name: String,
+        debug: bool,
+        ratio: f64,
+        hosts: Vec<String>,
+        ports: HashMap<String, u16>,
+        missing: Option<i32>,
+    }
+
+    // Output of PHP 7.4's var_export().
+    let input = "array (
+  'name' => 'app',
+  'debug' => false,
+  'ratio' => 0.5,
+  'hosts' => 
+  array (
+    0 => 'a.example.com',
+    1 => 'b.example.com',
+  ),
+  'ports' => 
+  array (
+    'http' => 80,
+    'https' => 443,
+  ),
+  'missing' => NULL,
+)";
+    let config: Config = serde_var_export::from_str(input).unwrap();
+    assert_eq!(
+        config,
+        Config {
+            name: "app".to_owned(),
+            debug: false,
+            ratio: 0.5,
+            hosts: vec!["a.example.com".to_owned(), "b.example.com".to_owned()],
+            ports: vec![("http".to_owned(), 80), ("https".to_owned(), 443)]
+                .into_iter()
+                .collect(),
+            missing: None,
+        }
+    );
+}
+
+#[test]
+fn round_trip() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Point(i32, i32),
+        Rect { w: u32, h: u32 },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Item {
+        name: String,
+        shapes: Vec<Shape>,
+        map: BTreeMap<i32, (bool, char)>,
+        bytes: Vec<u8>,
+        nested: Option<Box<Item>>,
+    }
+
+    let mut map = BTreeMap::new();
+    map.insert(-1, (true, 'x'));
+    map.insert(10, (false, '\''));
+    let item = Item {
+        name: "a'b\\c\n".to_owned(),
+        shapes: vec![
+            Shape::Empty,
+            Shape::Circle(1.5),
+            Shape::Point(1, -2),
+            Shape::Rect { w: 3, h: 4 },
+        ],
+        map,
+        bytes: b"php".to_vec(),
+        nested: Some(Box::new(Item {
+            name: String::new(),
+            shapes: vec![],
+            map: BTreeMap::new(),
+            bytes: vec![],
+            nested: None,
+        })),
+    };
+
+    let s = serde_var_export::to_string(&item).unwrap();
+    assert_eq!(serde_var_export::from_str::<Item>(&s).unwrap(), item);
+
+    let v = serde_var_export::to_vec(&item).unwrap();
+    assert_eq!(serde_var_export::from_slice::<Item>(&v).unwrap(), item);
+    assert_eq!(
+        serde_var_export::from_reader::<_, Item>(v.as_slice()).unwrap(),
+        item
+    );
+}
+
+#[test]
+fn deserialize_string_keys() {
+    let map: HashMap<String, i32> =
+        serde_var_export::from_str("array(0 => 1, '1' => 2, 'x' => 3)").unwrap();
+    assert_eq!(map["0"], 1);
+    assert_eq!(map["1"], 2);
+    assert_eq!(map["x"], 3);
+
+    let map: BTreeMap<i64, &str> =
+        serde_var_export::from_str("array('a', 'b', 5 => 'c', 'd', '1' => 'e')").unwrap();
+    assert_eq!(
+        map.into_iter().collect::<Vec<_>>(),
+        vec![(0, "a"), (1, "e"), (5, "c"), (6, "d")]
+    );
+}
+
+#[test]
+fn syntax_error() {
+    let err =
+        serde_var_export::from_str::<Vec<i32>>("array(\n  0 => 1,\n  1 => ?,\n)").unwrap_err();
+    assert!(err.is_syntax());
+    assert_eq!((err.line(), err.column()), (Some(3), Some(8)));
+
+    let err = serde_var_export::from_str::<i32>("1 2").unwrap_err();
+    assert_eq!(err.to_string(), "unexpected integer at line 1 column 3");
+
+    let err = serde_var_export::from_str::<String>("'abc").unwrap_err();
+    assert_eq!(err.to_string(), "unterminated string at line 1 column 1");
+}
+
+#[test]
+fn deserialize_php_literals() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        mode: i64,
+        mask: i64,
+        flags: i64,
+        big: i64,
+        scale: f64,
+        enabled: bool,
+        extra: Option<i32>,
+        tags: Vec<String>,
+        motd: String,
+        template: String,
+    }
+
+    let input = r#"[
+    // Hand-written config file.
+    'mode' => 0755,
+    'mask' => 0xFF, # hex
+    'flags' => 0b1010_0101,
+    'big' => 1_000_000,
+    /* scientific */
+    'scale' => 1e3,
+    'enabled' => TRUE,
+    'extra' => Null,
+    'tags' => ["a\tb", "\u{1F418}", "\x41\101\$"],
+    'motd' => <<<EOT
+        Hello
+          "world"\n
+        EOT,
+    'template' => <<<'EOT'
+    {$name}\n
+    EOT,
+]"#;
+    let config: Config = serde_var_export::from_str(input).unwrap();
+    assert_eq!(
+        config,
+        Config {
+            mode: 0o755,
+            mask: 0xff,
+            flags: 0b1010_0101,
+            big: 1_000_000,
+            scale: 1000.0,
+            enabled: true,
+            extra: None,
+            tags: vec!["a\tb".to_owned(), "\u{1F418}".to_owned(), "AA$".to_owned()],
+            motd: "Hello\n  \"world\"\n".to_owned(),
+            template: r"{$name}\n".to_owned(),
+        }
+    );
+}
+
+#[test]
+fn interpolation_error() {
+    let err = serde_var_export::from_str::<String>(r#""Hello $name""#).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "string interpolation is not supported at line 1 column 8"
+    );
+}
+
+#[test]
+fn case_insensitive_keywords() {
+    let v: Vec<Option<bool>> =
+        serde_var_export::from_str("Array(True, FALSE, null, \\true)").unwrap();
+    assert_eq!(v, vec![Some(true), Some(false), None, Some(true)]);
+}
+
+#[test]
+fn deserialize_set_state_objects() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Circle {
+            radius: f64,
+        },
+        #[serde(rename = "Geometry\\Square")]
+        Square(Point),
+        Empty,
+    }
+
+    // Output of PHP 7.4's var_export() for objects.
+    let point: Point = serde_var_export::from_str(
+        "\\App\\Point::__set_state(array(
+   'x' => 1,
+   'y' => 2,
+))",
+    )
+    .unwrap();
+    assert_eq!(point, Point { x: 1, y: 2 });
+
+    let shapes: Vec<Shape> = serde_var_export::from_str(
+        "array (
+  0 => 
+  \\Geometry\\Circle::__set_state(array(
+     'radius' => 1.5,
+  )),
+  1 => 
+  \\Geometry\\Square::__set_state(array(
+     'x' => 0,
+     'y' => 1,
+  )),
+  2 => 
+  \\Geometry\\Empty::__set_state(array(
+  )),
+  3 => 'Empty',
+)",
+    )
+    .unwrap();
+    assert_eq!(
+        shapes,
+        vec![
+            Shape::Circle { radius: 1.5 },
+            Shape::Square(Point { x: 0, y: 1 }),
+            Shape::Empty,
+            Shape::Empty,
+        ]
+    );
+
+    let err =
+        serde_var_export::from_str::<Shape>("\\Geometry\\Line::__set_state(array())").unwrap_err();
+    assert_eq!(err.kind(), serde_var_export::ErrorKind::UnknownClass);
+    assert_eq!(
+        err.to_string(),
+        "unknown class Geometry\\Line at line 1 column 1"
+    );
+
+    let err = serde_var_export::from_str::<Shape>("\\Foo::bar()").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unsupported method call at line 1 column 7"
+    );
+}
+
+#[test]
+fn deserialize_enum_cases() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Suit {
+        Hearts,
+        Spades,
+    }
+
+    let suits: Vec<Suit> =
+        serde_var_export::from_str("array(\\Suit::Hearts, Suit::Spades)").unwrap();
+    assert_eq!(suits, vec![Suit::Hearts, Suit::Spades]);
+
+    let err = serde_var_export::from_str::<Suit>("\\Suit::Clubs").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unknown variant `Clubs`, expected `Hearts` or `Spades` at line 1 column 1"
+    );
+}
+
+#[test]
+fn deserialize_std_class() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct User {
+        name: String,
+        roles: Vec<String>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Event {
+        Login(User),
+    }
+
+    // Output of var_export(json_decode(...)) in PHP 7.3+ and in older versions.
+    let inputs = [
+        "(object) array(
+   'name' => 'ann',
+   'roles' => 
+  array (
+    0 => 'admin',
+  ),
+)",
+        "\\stdClass::__set_state(array(
+   'name' => 'ann',
+   'roles' => 
+  array (
+    0 => 'admin',
+  ),
+))",
+    ];
+    for input in inputs.iter() {
+        let user: User = serde_var_export::from_str(input).unwrap();
+        assert_eq!(
+            user,
+            User {
+                name: "ann".to_owned(),
+                roles: vec!["admin".to_owned()],
+            }
+        );
+    }
+
+    let roles: Vec<String> = serde_var_export::from_str("(object) ['a', 'b']").unwrap();
+    assert_eq!(roles, vec!["a", "b"]);
+
+    let event: Event = serde_var_export::from_str(
+        "(object) ['Login' => (object) ['name' => 'bob', 'roles' => []]]",
+    )
+    .unwrap();
+    assert_eq!(
+        event,
+        Event::Login(User {
+            name: "bob".to_owned(),
+            roles: vec![],
+        })
+    );
+
+    let err = serde_var_export::from_str::<User>("(object) 'a'").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "only arrays can be cast to object at line 1 column 1"
+    );
+}
+
+fn from_str_with<'de, T, F>(s: &'de str, f: F) -> serde_var_export::Result<T>
+where
+    T: serde::Deserialize<'de>,
+    F: FnOnce(&mut serde_var_export::Deserializer<'de>),
+{
+    let mut deserializer = serde_var_export::Deserializer::from_str(s);
+    f(&mut deserializer);
+    let value = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+#[test]
+fn deserialize_lists() {
+    let list: Vec<&str> = serde_var_export::from_str("array(1 => 'b', 0 => 'a')").unwrap();
+    assert_eq!(list, vec!["a", "b"]);
+
+    let err = from_str_with::<Vec<&str>, _>("array(1 => 'b', 0 => 'a')", |de| {
+        de.strict_list_order(true);
+    })
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected key 0 in list, found key 1 at line 1 column 1"
+    );
+
+    let err = serde_var_export::from_str::<Vec<&str>>("array(0 => 'a', 5 => 'b')").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected a list, key 1 is missing at line 1 column 1"
+    );
+
+    let err = serde_var_export::from_str::<Vec<&str>>("array(1 => 'a')").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected a list, key 0 is missing at line 1 column 1"
+    );
+
+    let err = serde_var_export::from_str::<Vec<i32>>("array('a' => 1)").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected a list, found key 'a' at line 1 column 1"
+    );
+
+    let err = serde_var_export::from_str::<(i32, i32)>("array(-1 => 1, 0 => 2)").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected a list, found key -1 at line 1 column 1"
+    );
+}
+
+#[test]
+fn fill_list_gaps() {
+    let list: Vec<Option<&str>> = from_str_with("array(3 => 'd', 1 => 'b')", |de| {
+        de.fill_list_gaps(true);
+    })
+    .unwrap();
+    assert_eq!(list, vec![None, Some("b"), None, Some("d")]);
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Row {
+        ids: Vec<u32>,
+        names: Vec<String>,
+        flags: Vec<bool>,
+        nested: Vec<Vec<f64>>,
+    }
+    let row: Row = from_str_with(
+        "array(
+  'ids' => array(2 => 7),
+  'names' => array(1 => 'x'),
+  'flags' => array(true, 2 => true),
+  'nested' => array(1 => array(1.5)),
+)",
+        |de| {
+            de.fill_list_gaps(true).strict_list_order(true);
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        row,
+        Row {
+            ids: vec![0, 0, 7],
+            names: vec![String::new(), "x".to_owned()],
+            flags: vec![true, false, true],
+            nested: vec![vec![], vec![1.5]],
+        }
+    );
+
+    let list: Vec<i32> = from_str_with("array(0 => 1, 1026 => 2)", |de| {
+        de.fill_list_gaps(true);
+    })
+    .unwrap();
+    assert_eq!(list.len(), 1027);
+    let err = from_str_with::<Vec<i32>, _>("array(0 => 1, 4000000000000 => 2)", |de| {
+        de.fill_list_gaps(true);
+    })
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "too many missing keys in list, up to key 4000000000000 at line 1 column 1"
+    );
+
+    let err = from_str_with::<Vec<i32>, _>("array(2 => 1, 0 => 2)", |de| {
+        de.fill_list_gaps(true).strict_list_order(true);
+    })
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected key 3 in list, found key 0 at line 1 column 1"
+    );
+}
+
+#[test]
+fn deserialize_any() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(untagged)]
+    enum Setting {
+        Flag(bool),
+        Number(i64),
+        Ratio(f64),
+        Text(String),
+        List(Vec<Setting>),
+        Table(BTreeMap<String, Setting>),
+        Indexed(BTreeMap<i64, Setting>),
+        Missing(()),
+    }
+
+    let settings: Setting = serde_var_export::from_str(
+        "array(
+  'debug' => true,
+  'workers' => 4,
+  'ratio' => 0.5,
+  'name' => 'app',
+  'hosts' => array('a', 'b'),
+  'ports' => array(1 => 80, 0 => 443),
+  'empty' => array(),
+  'cache' => NULL,
+)",
+    )
+    .unwrap();
+    let table = |entries: Vec<(&str, Setting)>| {
+        Setting::Table(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+        )
+    };
+    assert_eq!(
+        settings,
+        table(vec![
+            ("debug", Setting::Flag(true)),
+            ("workers", Setting::Number(4)),
+            ("ratio", Setting::Ratio(0.5)),
+            ("name", Setting::Text("app".to_owned())),
+            (
+                "hosts",
+                Setting::List(vec![
+                    Setting::Text("a".to_owned()),
+                    Setting::Text("b".to_owned()),
+                ])
+            ),
+            (
+                "ports",
+                Setting::Indexed(
+                    vec![(1, Setting::Number(80)), (0, Setting::Number(443))]
+                        .into_iter()
+                        .collect()
+                )
+            ),
+            ("empty", Setting::List(vec![])),
+            ("cache", Setting::Missing(())),
+        ])
+    );
+
+    // Untagged enums see integer keys as integers only.
+    #[derive(Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    #[serde(untagged)]
+    enum Key {
+        Int(i64),
+        Name(String),
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(untagged)]
+    enum Map {
+        Named(BTreeMap<String, i32>),
+        Mixed(BTreeMap<Key, i32>),
+    }
+
+    let nested: BTreeMap<String, BTreeMap<String, i32>> =
+        serde_var_export::from_str("array('m' => array(1 => 2))").unwrap();
+    assert_eq!(nested["m"]["1"], 2);
+    let map: Map = serde_var_export::from_str("array('a' => 1, 2 => 3)").unwrap();
+    let keys: Vec<_> = match map {
+        Map::Mixed(map) => map.into_keys().collect(),
+        Map::Named(_) => panic!("integer key read as string"),
+    };
+    assert_eq!(keys, [Key::Int(2), Key::Name("a".to_owned())]);
+
+    let hosts: Setting = from_str_with("array('a')", |de| {
+        de.arrays_as_maps(true);
+    })
+    .unwrap();
+    assert_eq!(
+        hosts,
+        Setting::Indexed(
+            vec![(0, Setting::Text("a".to_owned()))]
+                .into_iter()
+                .collect()
+        )
+    );
+}
+
+#[test]
+fn error_position_and_snippet() {
+    #[derive(Deserialize, Debug)]
+    #[serde(deny_unknown_fields)]
+    #[allow(dead_code)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    let input = "array (
+  'host' => 'localhost',
+  'port' => '80',
+)";
+    let err = serde_var_export::from_str::<Server>(input).unwrap_err();
+    assert!(err.is_data());
+    assert_eq!(
+        err.to_string(),
+        "invalid type: string \"80\", expected u16 at line 3 column 13"
+    );
+    assert_eq!(err.offset(), Some(input.find("'80'").unwrap()));
+    assert_eq!(
+        err.snippet(),
+        Some("3 |   'port' => '80',\n  |             ^")
+    );
+
+    let err = serde_var_export::from_str::<Server>("array(\n\t'host' => 'a',\n)").unwrap_err();
+    assert_eq!(err.to_string(), "missing field `port` at line 1 column 1");
+
+    let err = serde_var_export::from_str::<Server>("array(\n\t'hots' => 'a',\n)").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unknown field `hots`, expected `host` or `port` at line 2 column 12"
+    );
+
+    let err = serde_var_export::from_str::<Vec<u8>>("array(\n\t1,\n\t-1,\n)").unwrap_err();
+    assert_eq!(err.snippet(), Some("3 | \t-1,\n  | \t^"));
+    assert_eq!((err.line(), err.column()), (Some(3), Some(2)));
+
+    let err = serde_var_export::from_str::<Vec<u8>>("array(\n  1 => ?,\n)").unwrap_err();
+    assert!(err.is_syntax());
+    assert_eq!(err.offset(), Some(14));
+    assert_eq!(err.snippet(), Some("2 |   1 => ?,\n  |        ^"));
+}
+
+#[test]
+fn deeply_nested_input() {
+    let err = serde_var_export::from_str::<i64>(&("-".repeat(200_000) + "1")).unwrap_err();
+    assert_eq!(err.kind(), serde_var_export::ErrorKind::Syntax);
+    assert_eq!(
+        err.to_string(),
+        "recursion limit exceeded at line 1 column 129"
+    );
+}
+
+#[test]
+fn deserialize_php_int_min() {
+    assert_eq!(
+        serde_var_export::from_str::<i64>("-9223372036854775807-1").unwrap(),
+        i64::MIN
+    );
+    assert_eq!(
+        serde_var_export::from_str::<Vec<i64>>(
+            "array(\n  0 => -9223372036854775807-1,\n  1 => 2,\n)"
+        )
+        .unwrap(),
+        vec![i64::MIN, 2]
+    );
+    let err = serde_var_export::from_str::<i64>("'a' - 1").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "only integers can be subtracted at line 1 column 5"
+    );
+}
+
+#[test]
+fn deeply_nested_set_state() {
+    let input = "\\A::__set_state(".repeat(100_000);
+    let err = serde_var_export::from_str::<serde_var_export::Value>(&input).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "recursion limit exceeded at line 1 column 2049"
+    );
+}
+
+#[test]
+fn deeply_nested_object_casts() {
+    let input = "(object)".repeat(200_000) + "array()";
+    let err = serde_var_export::from_str::<serde_var_export::Value>(&input).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "recursion limit exceeded at line 1 column 1025"
+    );
+}
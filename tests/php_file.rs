@@ -0,0 +1,100 @@
+use serde_derive::Deserialize;
+use std::collections::BTreeMap;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Database {
+    driver: String,
+    port: u16,
+    options: BTreeMap<String, bool>,
+}
+
+const CONFIG: &str = r#"<?php
+
+declare(strict_types=1);
+
+namespace App\Config;
+
+use App\Support\Env;
+use function App\{env, base_path};
+
+/*
+ * Database settings.
+ */
+return [
+    'driver' => 'mysql',
+    'port' => 3306,
+    'options' => [
+        'persistent' => true,
+    ],
+];
+"#;
+
+fn database() -> Database {
+    Database {
+        driver: "mysql".to_owned(),
+        port: 3306,
+        options: vec![("persistent".to_owned(), true)].into_iter().collect(),
+    }
+}
+
+#[test]
+fn from_php_file_str() {
+    assert_eq!(
+        serde_var_export::from_php_file_str::<Database>(CONFIG).unwrap(),
+        database()
+    );
+    assert_eq!(
+        serde_var_export::from_php_file_str::<i32>("<?PHP return 1 ?>\n").unwrap(),
+        1
+    );
+    assert_eq!(
+        serde_var_export::from_php_file_str::<i32>("#!/usr/bin/env php\n<?php return 2;\n?>\n")
+            .unwrap(),
+        2
+    );
+}
+
+#[test]
+fn from_php_file() {
+    let path = std::env::temp_dir().join("serde_var_export_database.php");
+    std::fs::write(&path, CONFIG).unwrap();
+    let config = serde_var_export::from_php_file::<_, Database>(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(config.unwrap(), database());
+
+    let err = serde_var_export::from_php_file::<_, Database>(&path).unwrap_err();
+    assert!(err.is_io());
+}
+
+#[test]
+fn unsupported_statements() {
+    let errors = [
+        ("return 1;", "expected `<?php` open tag at line 1 column 1"),
+        ("<?php\n", "missing `return` statement at line 2 column 1"),
+        (
+            "<?php\n$a = 1;\nreturn $a;",
+            "unsupported statement, expected `return` at line 2 column 1",
+        ),
+        (
+            "<?php\nrequire 'x.php';\nreturn 1;",
+            "unsupported statement `require`, expected `return` at line 2 column 1",
+        ),
+        (
+            "<?php\nnamespace App {\n}",
+            "unsupported `namespace` block at line 2 column 15",
+        ),
+        (
+            "<?php return 1;\necho 2;",
+            "unexpected `echo` at line 2 column 1",
+        ),
+        (
+            "<?php return 1; ?>\n<p>",
+            "unexpected output after `?>` at line 1 column 17",
+        ),
+    ];
+    for (input, message) in errors.iter() {
+        let err = serde_var_export::from_php_file_str::<i32>(input).unwrap_err();
+        assert!(err.is_syntax());
+        assert_eq!(err.to_string(), *message, "{}", input);
+    }
+}
@@ -0,0 +1,548 @@
+use serde::Serialize;
+use serde_derive::Serialize;
+use serde_var_export::{Error, ErrorKind, RawPhp, Serializer};
+use std::collections::BTreeMap;
+
+#[derive(Serialize)]
+struct Foo {
+    names: Vec<String>,
+    nums: Vec<i32>,
+}
+
+fn foo() -> Foo {
+    Foo {
+        names: vec!["hello".to_owned(), "world".to_owned()],
+        nums: vec![1, 2, 3],
+    }
+}
+
+fn to_string_with<T, F>(value: &T, f: F) -> String
+where
+    T: Serialize,
+    F: FnOnce(&mut Serializer<Vec<u8>>),
+{
+    let mut serializer = Serializer::new(Vec::new());
+    f(&mut serializer);
+    value.serialize(&mut serializer).unwrap();
+    String::from_utf8(serializer.into_inner()).unwrap()
+}
+
+#[test]
+fn line_width_inline() {
+    let s = to_string_with(&foo(), |ser| {
+        ser.line_width(Some(100));
+    });
+    assert_eq!(
+        s,
+        "array('names' => array(0 => 'hello', 1 => 'world'), 'nums' => array(0 => 1, 1 => 2, 2 => 3))"
+    );
+}
+
+#[test]
+fn line_width_break() {
+    let s = to_string_with(&foo(), |ser| {
+        ser.line_width(Some(50));
+    });
+    assert_eq!(
+        s,
+        "array(
+  'names' => array(0 => 'hello', 1 => 'world'),
+  'nums' => array(0 => 1, 1 => 2, 2 => 3),
+)"
+    );
+
+    let s = to_string_with(&foo(), |ser| {
+        ser.line_width(Some(35));
+    });
+    assert_eq!(
+        s,
+        "array(
+  'names' =>\n  array(
+    0 => 'hello',
+    1 => 'world',
+  ),
+  'nums' =>\n  array(0 => 1, 1 => 2, 2 => 3),
+)"
+    );
+}
+
+#[test]
+fn line_width_empty_and_multiline() {
+    let value = (Vec::<i32>::new(), "a\nb");
+    let s = to_string_with(&value, |ser| {
+        ser.line_width(Some(100));
+    });
+    assert_eq!(
+        s,
+        "array(
+  0 => array(),
+  1 => 'a\nb',
+)"
+    );
+}
+
+#[test]
+fn align_arrows() {
+    #[derive(Serialize)]
+    struct Config {
+        driver: &'static str,
+        host: &'static str,
+        port: u16,
+        options: Vec<u8>,
+    }
+
+    let config = Config {
+        driver: "mysql",
+        host: "localhost",
+        port: 3306,
+        options: vec![1; 11],
+    };
+    let s = to_string_with(&config, |ser| {
+        ser.align_arrows(true);
+    });
+    assert_eq!(
+        s,
+        "array(
+  'driver'  => 'mysql',
+  'host'    => 'localhost',
+  'port'    => 3306,
+  'options' =>\n  array(
+    0  => 1,
+    1  => 1,
+    2  => 1,
+    3  => 1,
+    4  => 1,
+    5  => 1,
+    6  => 1,
+    7  => 1,
+    8  => 1,
+    9  => 1,
+    10 => 1,
+  ),
+)"
+    );
+}
+
+#[test]
+fn align_arrows_with_line_width() {
+    let s = to_string_with(&foo(), |ser| {
+        ser.align_arrows(true).line_width(Some(50));
+    });
+    assert_eq!(
+        s,
+        "array(
+  'names' => array(0 => 'hello', 1 => 'world'),
+  'nums'  => array(0 => 1, 1 => 2, 2 => 3),
+)"
+    );
+}
+
+#[test]
+fn same_line_arrays() {
+    let s = to_string_with(&foo(), |ser| {
+        ser.same_line_arrays(true);
+    });
+    assert_eq!(
+        s,
+        "array(
+  'names' => array(
+    0 => 'hello',
+    1 => 'world',
+  ),
+  'nums' => array(
+    0 => 1,
+    1 => 2,
+    2 => 3,
+  ),
+)"
+    );
+
+    let s = to_string_with(&foo(), |ser| {
+        ser.same_line_arrays(true).line_width(Some(35));
+    });
+    assert_eq!(
+        s,
+        "array(
+  'names' => array(
+    0 => 'hello',
+    1 => 'world',
+  ),
+  'nums' => array(
+    0 => 1,
+    1 => 2,
+    2 => 3,
+  ),
+)"
+    );
+}
+
+#[test]
+fn no_trailing_whitespace() {
+    for same_line in &[false, true] {
+        let s = to_string_with(&(foo(), vec![foo()]), |ser| {
+            ser.same_line_arrays(*same_line);
+        });
+        assert!(s.lines().all(|line| !line.ends_with(' ')), "{}", s);
+    }
+}
+
+#[test]
+fn skip_null() {
+    #[derive(Serialize)]
+    struct Item {
+        name: Option<&'static str>,
+        unit: (),
+        list: Vec<Option<i32>>,
+        map: BTreeMap<&'static str, Option<i32>>,
+    }
+
+    let mut map = BTreeMap::new();
+    map.insert("a", None);
+    map.insert("b", Some(2));
+    let item = Item {
+        name: None,
+        unit: (),
+        list: vec![None, Some(1)],
+        map,
+    };
+
+    let s = to_string_with(&item, |ser| {
+        ser.skip_null(true);
+    });
+    assert_eq!(
+        s,
+        "array(
+  'list' =>
+  array(
+    0 => NULL,
+    1 => 1,
+  ),
+  'map' =>
+  array(
+    'b' => 2,
+  ),
+)"
+    );
+
+    let s = to_string_with(&item, |ser| {
+        ser.skip_null(true).line_width(Some(100));
+    });
+    assert_eq!(
+        s,
+        "array('list' => array(0 => NULL, 1 => 1), 'map' => array('b' => 2))"
+    );
+}
+
+#[test]
+fn raw_php() {
+    #[derive(Serialize)]
+    struct Config {
+        host: RawPhp,
+        eol: RawPhp,
+        errmode: RawPhp,
+        paths: RawPhp,
+        now: RawPhp,
+    }
+
+    let config = Config {
+        host: RawPhp::call("env", &("DB_HOST", "localhost")).unwrap(),
+        eol: RawPhp::constant("PHP_EOL"),
+        errmode: RawPhp::class_constant("\\PDO", "ERRMODE_EXCEPTION"),
+        paths: RawPhp::call("array_merge", &(vec!["a"], vec!["b\nc"])).unwrap(),
+        now: RawPhp::call("time", &()).unwrap(),
+    };
+    let expect = "array(
+  'host' => env('DB_HOST', 'localhost'),
+  'eol' => PHP_EOL,
+  'errmode' => \\PDO::ERRMODE_EXCEPTION,
+  'paths' => array_merge(array(0 => 'a'), array(0 => 'b\nc')),
+  'now' => time(),
+)";
+    assert_eq!(serde_var_export::to_string(&config).unwrap(), expect);
+    assert_eq!(
+        to_string_with(&config, |ser| {
+            ser.align_arrows(true);
+        }),
+        expect
+            .replace("'eol' =>", "'eol'     =>")
+            .replace("'host' =>", "'host'    =>")
+            .replace("'paths' =>", "'paths'   =>")
+            .replace("'now' =>", "'now'     =>")
+    );
+    assert_eq!(RawPhp::call("f", &1).unwrap().as_str(), "f(1)");
+}
+
+#[test]
+fn raw_php_call_with_array_argument() {
+    #[derive(Serialize)]
+    struct Opts {
+        a: i32,
+        b: &'static str,
+    }
+
+    assert_eq!(
+        RawPhp::call("f", &Opts { a: 1, b: "x" }).unwrap().as_str(),
+        "f(array('a' => 1, 'b' => 'x'))"
+    );
+    let mut map = BTreeMap::new();
+    map.insert("k", vec![1]);
+    assert_eq!(
+        RawPhp::call("f", &map).unwrap().as_str(),
+        "f(array('k' => array(0 => 1)))"
+    );
+    assert_eq!(
+        RawPhp::call("f", &(map, 2)).unwrap().as_str(),
+        "f(array('k' => array(0 => 1)), 2)"
+    );
+}
+
+#[test]
+fn nowdoc_strings() {
+    #[derive(Serialize)]
+    struct Mail {
+        subject: &'static str,
+        body: &'static str,
+        queries: Vec<&'static str>,
+    }
+
+    let mail = Mail {
+        subject: "Hello",
+        body: "Hi,\n\nEOT is near.\n",
+        queries: vec!["SELECT *\nFROM 'users'"],
+    };
+    let s = to_string_with(&mail, |ser| {
+        ser.nowdoc_strings(true);
+    });
+    assert_eq!(
+        s,
+        "array(
+  'subject' => 'Hello',
+  'body' => <<<'EOT1'
+  Hi,
+
+  EOT is near.
+
+  EOT1,
+  'queries' =>
+  array(
+    0 => <<<'EOT'
+    SELECT *
+    FROM 'users'
+    EOT,
+  ),
+)"
+    );
+
+    let s = to_string_with(&mail.queries, |ser| {
+        ser.nowdoc_strings(true).line_width(Some(100));
+    });
+    assert_eq!(
+        s,
+        "array(
+  0 => <<<'EOT'
+  SELECT *
+  FROM 'users'
+  EOT,
+)"
+    );
+}
+
+#[test]
+fn escape_control_chars() {
+    let value = vec![
+        "plain 'quoted' $x",
+        "tab\there\r\n",
+        "\0\x7f \"$x\" \\",
+        "a\nb",
+    ];
+    let s = to_string_with(&value, |ser| {
+        ser.escape_control_chars(true);
+    });
+    assert_eq!(
+        s,
+        r#"array(
+  0 => 'plain \'quoted\' $x',
+  1 => "tab\there\r\n",
+  2 => "\x00\x7F \"\$x\" \\",
+  3 => "a\nb",
+)"#
+    );
+
+    let s = to_string_with(&value, |ser| {
+        ser.escape_control_chars(true).nowdoc_strings(true);
+    });
+    assert_eq!(
+        s,
+        r#"array(
+  0 => 'plain \'quoted\' $x',
+  1 => "tab\there\r\n",
+  2 => "\x00\x7F \"\$x\" \\",
+  3 => <<<'EOT'
+  a
+  b
+  EOT,
+)"#
+    );
+}
+
+#[test]
+fn ascii_only() {
+    let mut value = BTreeMap::new();
+    value.insert("café", "😀\t$");
+    value.insert("plain", "tab\t");
+    let s = to_string_with(&value, |ser| {
+        ser.ascii_only(true);
+    });
+    assert_eq!(
+        s,
+        r#"array(
+  "caf\u{E9}" => "\u{1F600}\t\$",
+  'plain' => 'tab	',
+)"#
+    );
+    assert!(s.is_ascii());
+}
+
+#[test]
+fn max_depth() {
+    #[derive(Serialize)]
+    struct Node {
+        name: &'static str,
+        children: Vec<Node>,
+    }
+
+    let tree = Node {
+        name: "a",
+        children: vec![Node {
+            name: "b",
+            children: vec![],
+        }],
+    };
+
+    for line_width in &[None, Some(100)] {
+        let mut serializer = Serializer::new(Vec::new());
+        serializer.max_depth(Some(4)).line_width(*line_width);
+        tree.serialize(&mut serializer).unwrap();
+
+        let mut serializer = Serializer::new(Vec::new());
+        serializer.max_depth(Some(3)).line_width(*line_width);
+        match tree.serialize(&mut serializer).unwrap_err() {
+            Error::DepthLimitExceeded { limit, path } => {
+                assert_eq!(limit, 3);
+                assert_eq!(path, "children[0].children");
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+    }
+}
+
+#[test]
+fn max_bytes() {
+    let value = vec!["x"; 100];
+    let len = serde_var_export::to_string(&value).unwrap().len();
+
+    for line_width in &[None, Some(100)] {
+        let mut serializer = Serializer::new(Vec::new());
+        serializer.max_bytes(Some(len)).line_width(*line_width);
+        value.serialize(&mut serializer).unwrap();
+
+        let mut serializer = Serializer::new(Vec::new());
+        serializer.max_bytes(Some(64)).line_width(*line_width);
+        match value.serialize(&mut serializer).unwrap_err() {
+            Error::SizeLimitExceeded { limit } => assert_eq!(limit, 64),
+            err => panic!("unexpected error: {}", err),
+        }
+        assert!(serializer.into_inner().len() <= 64);
+    }
+}
+
+#[test]
+fn error_path() {
+    struct Port(i64);
+
+    impl Serialize for Port {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            if self.0 < 0 {
+                return Err(serde::ser::Error::custom("invalid value"));
+            }
+            serializer.serialize_i64(self.0)
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Connection {
+        port: Port,
+    }
+
+    #[derive(Serialize)]
+    struct Database {
+        connections: Vec<Connection>,
+    }
+
+    #[derive(Serialize)]
+    struct Config {
+        database: Database,
+    }
+
+    let config = Config {
+        database: Database {
+            connections: vec![
+                Connection { port: Port(1) },
+                Connection { port: Port(2) },
+                Connection { port: Port(-1) },
+            ],
+        },
+    };
+    let err = serde_var_export::to_string(&config).unwrap_err();
+    assert_eq!(err.path(), Some("database.connections[2].port"));
+    assert_eq!(
+        err.to_string(),
+        "at database.connections[2].port: invalid value"
+    );
+
+    let err = serde_var_export::to_string(&Port(-1)).unwrap_err();
+    assert_eq!(err.path(), None);
+    assert_eq!(err.to_string(), "invalid value");
+}
+
+#[test]
+fn non_finite_floats() {
+    let floats = vec![f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 1.5];
+    let s = serde_var_export::to_string(&floats).unwrap();
+    assert_eq!(
+        s,
+        "array(
+  0 => NAN,
+  1 => INF,
+  2 => -INF,
+  3 => 1.5,
+)"
+    );
+    let read: Vec<f64> = serde_var_export::from_str(&s).unwrap();
+    assert!(read[0].is_nan());
+    assert_eq!(read[1..], floats[1..]);
+    assert_eq!(
+        serde_var_export::to_string(&f32::NEG_INFINITY).unwrap(),
+        "-INF"
+    );
+}
+
+#[test]
+fn error_kind() {
+    let err = serde_var_export::to_string(&vec![1, i128::MAX]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::IntegerOverflow);
+    assert_eq!(err.path(), Some("[1]"));
+    assert_eq!(serde_var_export::to_string(&-1i128).unwrap(), "-1");
+    // PHP reads integers overflowing int as floats.
+    assert_eq!(
+        serde_var_export::to_string(&u64::MAX).unwrap(),
+        "18446744073709551615"
+    );
+
+    let mut map = BTreeMap::new();
+    map.insert(vec![1], 1);
+    let err = serde_var_export::to_string(&map).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidKey);
+    assert!(err.is_data());
+    assert!(!err.is_io());
+}
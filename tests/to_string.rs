@@ -16,7 +16,7 @@ fn serialize_tuple_variant() {
     assert_eq!(
         s,
         "array(
-  'Bar' => \n  array(
+  'Bar' =>\n  array(
     0 => 100,
     1 => '\\'bar\\'',
   ),
@@ -59,7 +59,7 @@ fn serialize_struct_variant() {
     assert_eq!(
         s,
         "array(
-  'Bar' => \n  array(
+  'Bar' =>\n  array(
     'name' => 'nnn',
     'value' => 100,
   ),
@@ -115,61 +115,61 @@ fn test_complex_0() {
 
     let s = serde_var_export::to_string(&complex).unwrap();
     let s0 = "array(
-  'map' => \n  array(
-    0 => \n    array(
+  'map' =>\n  array(
+    0 =>\n    array(
       'name' => 'foo',
       'age' => 100,
       'is' => true,
     ),
-    10 => \n    array(
+    10 =>\n    array(
       'name' => '\\'bar\\\\\n\\'',
       'age' => 200,
       'is' => false,
     ),
   ),
-  'list' => \n  array(
-    0 => \n    array(
+  'list' =>\n  array(
+    0 =>\n    array(
       'name' => 'foo',
       'age' => 100,
       'is' => true,
     ),
-    1 => \n    array(
+    1 =>\n    array(
       'name' => '\\'bar\\\\\n\\'',
       'age' => 200,
       'is' => false,
     ),
   ),
   'num' => NULL,
-  'empty' => \n  array(
+  'empty' =>\n  array(
   ),
 )";
     let s1 = "array(
-  'map' => \n  array(
-    10 => \n    array(
+  'map' =>\n  array(
+    10 =>\n    array(
       'name' => '\\'bar\\\\\n\\'',
       'age' => 200,
       'is' => false,
     ),
-    0 => \n    array(
+    0 =>\n    array(
       'name' => 'foo',
       'age' => 100,
       'is' => true,
     ),
   ),
-  'list' => \n  array(
-    0 => \n    array(
+  'list' =>\n  array(
+    0 =>\n    array(
       'name' => 'foo',
       'age' => 100,
       'is' => true,
     ),
-    1 => \n    array(
+    1 =>\n    array(
       'name' => '\\'bar\\\\\n\\'',
       'age' => 200,
       'is' => false,
     ),
   ),
   'num' => NULL,
-  'empty' => \n  array(
+  'empty' =>\n  array(
   ),
 )";
 
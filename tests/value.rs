@@ -0,0 +1,404 @@
+use serde_derive::{Deserialize, Serialize};
+use serde_var_export::{ArrayKey, PhpArray, RawPhp, Value};
+use std::collections::BTreeMap;
+
+fn string(s: &str) -> Value {
+    Value::String(s.as_bytes().to_vec())
+}
+
+#[test]
+fn deserialize_value() {
+    let value: Value = serde_var_export::from_str(
+        r"array(
+  'name' => 'app',
+  'debug' => false,
+  'ratio' => 0.5,
+  'cache' => NULL,
+  'hosts' => array(1 => 'a', 0 => 'b'),
+  'pdo' => \PDO::__set_state(array('dsn' => 'mysql:')),
+  'point' => (object) array('x' => 1),
+  'suit' => \Cards\Suit::Hearts,
+)",
+    )
+    .unwrap();
+
+    let hosts: PhpArray = vec![
+        (ArrayKey::Int(1), string("a")),
+        (ArrayKey::Int(0), string("b")),
+    ]
+    .into_iter()
+    .collect();
+    let pdo: PhpArray = vec![(ArrayKey::from("dsn"), string("mysql:"))]
+        .into_iter()
+        .collect();
+    let point: PhpArray = vec![(ArrayKey::from("x"), Value::Int(1))]
+        .into_iter()
+        .collect();
+    let expected: PhpArray = vec![
+        (ArrayKey::from("name"), string("app")),
+        (ArrayKey::from("debug"), Value::Bool(false)),
+        (ArrayKey::from("ratio"), Value::Float(0.5)),
+        (ArrayKey::from("cache"), Value::Null),
+        (ArrayKey::from("hosts"), Value::Array(hosts)),
+        (
+            ArrayKey::from("pdo"),
+            Value::Object {
+                class: "PDO".to_owned(),
+                properties: pdo,
+            },
+        ),
+        (
+            ArrayKey::from("point"),
+            Value::Object {
+                class: "stdClass".to_owned(),
+                properties: point,
+            },
+        ),
+        (
+            ArrayKey::from("suit"),
+            Value::Enum {
+                class: "Cards\\Suit".to_owned(),
+                case: "Hearts".to_owned(),
+            },
+        ),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(value, Value::Array(expected));
+}
+
+#[test]
+fn serialize_value() {
+    let s = r#"array(
+  'pdo' =>
+  \PDO::__set_state(array(
+    'dsn' => 'mysql:',
+    'options' =>
+    array(
+    ),
+  )),
+  'point' =>
+  (object) array(
+    'x' => 1,
+  ),
+  'suit' => \Suit::Hearts,
+  'bytes' => "\xFF\$",
+)"#;
+    let value: Value = serde_var_export::from_str(s).unwrap();
+    assert_eq!(serde_var_export::to_string(&value).unwrap(), s);
+}
+
+#[test]
+fn serialize_value_with_line_width() {
+    let value: Value = serde_var_export::from_str(
+        r"array(
+  'pdo' => \PDO::__set_state(array('dsn' => 'mysql:')),
+  'point' => (object) array('x' => 1, 'y' => 2),
+)",
+    )
+    .unwrap();
+    let mut buf = Vec::new();
+    let mut serializer = serde_var_export::Serializer::new(&mut buf);
+    serializer.line_width(Some(80));
+    serde::Serialize::serialize(&value, &mut serializer).unwrap();
+    assert_eq!(
+        String::from_utf8(buf).unwrap(),
+        r"array(
+  'pdo' => \PDO::__set_state(array('dsn' => 'mysql:')),
+  'point' => (object) array('x' => 1, 'y' => 2),
+)"
+    );
+}
+
+#[test]
+fn to_value_and_from_value() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Driver {
+        Mysql { host: String, port: u16 },
+        Sqlite(String),
+        Memory,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Config {
+        debug: bool,
+        drivers: Vec<Driver>,
+        ratio: Option<f64>,
+        tags: BTreeMap<String, i32>,
+    }
+
+    let config = Config {
+        debug: false,
+        drivers: vec![
+            Driver::Mysql {
+                host: "localhost".to_owned(),
+                port: 3306,
+            },
+            Driver::Sqlite("db.sqlite".to_owned()),
+            Driver::Memory,
+        ],
+        ratio: None,
+        tags: vec![("1".to_owned(), 1), ("b".to_owned(), 2)]
+            .into_iter()
+            .collect(),
+    };
+    let value = serde_var_export::to_value(&config).unwrap();
+    // Only the numeric string key differs, as PHP makes it an int.
+    assert_eq!(
+        serde_var_export::to_string(&value).unwrap(),
+        serde_var_export::to_string(&config)
+            .unwrap()
+            .replace("'1' =>", "1 =>")
+    );
+    match &value {
+        Value::Array(array) => match array.get("tags") {
+            Some(Value::Array(tags)) => assert_eq!(tags.get(1), Some(&Value::Int(1))),
+            tags => panic!("unexpected tags {:?}", tags),
+        },
+        value => panic!("unexpected value {:?}", value),
+    }
+    assert_eq!(
+        serde_var_export::from_value::<Config>(value).unwrap(),
+        config
+    );
+}
+
+#[test]
+fn value_to_value() {
+    let value: Value = serde_var_export::from_str(
+        r#"array(
+  'pdo' => \PDO::__set_state(array('dsn' => 'mysql:')),
+  'point' => (object) array('x' => 1),
+  'suit' => \Suit::Hearts,
+  'bytes' => "\xff",
+  5 => array(2 => 'a'),
+)"#,
+    )
+    .unwrap();
+    assert_eq!(serde_var_export::to_value(&value).unwrap(), value);
+    assert_eq!(
+        serde_var_export::from_value::<Value>(value.clone()).unwrap(),
+        value
+    );
+}
+
+#[test]
+fn from_value_like_from_str() {
+    use serde::Deserialize;
+    use serde_var_export::Deserializer;
+
+    let s = "array(1 => 'b', 0 => 'a', 3 => 'd')";
+    let value: Value = serde_var_export::from_str(s).unwrap();
+    let err = serde_var_export::from_value::<Vec<String>>(value.clone()).unwrap_err();
+    assert_eq!(err.to_string(), "expected a list, key 2 is missing");
+
+    let mut deserializer = Deserializer::from_value(&value);
+    deserializer.fill_list_gaps(true);
+    let list = Vec::<String>::deserialize(&mut deserializer).unwrap();
+    let mut deserializer = Deserializer::from_str(s);
+    deserializer.fill_list_gaps(true);
+    assert_eq!(Vec::<String>::deserialize(&mut deserializer).unwrap(), list);
+    assert_eq!(list, ["a", "b", "", "d"]);
+
+    let mut deserializer = Deserializer::from_value(&value);
+    deserializer.strict_list_order(true).fill_list_gaps(true);
+    let err = Vec::<String>::deserialize(&mut deserializer).unwrap_err();
+    assert_eq!(err.to_string(), "expected key 2 in list, found key 0");
+
+    let value = serde_var_export::to_value(&RawPhp::new("env('HOME')")).unwrap();
+    assert_eq!(
+        serde_var_export::from_value::<Value>(value.clone()).unwrap(),
+        value
+    );
+}
+
+#[test]
+fn to_value_errors() {
+    let err = serde_var_export::to_value(&u128::MAX).unwrap_err();
+    assert_eq!(err.kind(), serde_var_export::ErrorKind::IntegerOverflow);
+    assert_eq!(
+        serde_var_export::to_value(&u64::MAX).unwrap(),
+        Value::Float(u64::MAX as f64)
+    );
+
+    let mut map = BTreeMap::new();
+    map.insert(vec![1], 1);
+    let err = serde_var_export::to_value(&map).unwrap_err();
+    assert_eq!(err.kind(), serde_var_export::ErrorKind::InvalidKey);
+}
+
+#[test]
+fn raw_php_value() {
+    let mut config = BTreeMap::new();
+    config.insert("host", RawPhp::call("env", &("DB_HOST",)).unwrap());
+    config.insert("storage", RawPhp::new("__DIR__ . '/storage'"));
+    config.insert("mode", RawPhp::class_constant("PDO", "ATTR_ERRMODE"));
+    config.insert("name", RawPhp::new("'a' . 'b'"));
+    config.insert("ratio", RawPhp::new("NAN"));
+    let value = serde_var_export::to_value(&config).unwrap();
+    for (key, raw) in &config {
+        assert_eq!(value[*key], Value::Raw(raw.clone()));
+    }
+    assert_eq!(
+        serde_var_export::to_string(&value).unwrap(),
+        serde_var_export::to_string(&config).unwrap()
+    );
+
+    let err = serde_var_export::from_value::<String>(value["host"].clone()).unwrap_err();
+    assert_eq!(err.to_string(), "raw PHP `env('DB_HOST')` is not a value");
+}
+
+#[test]
+fn php_macro() {
+    use serde_var_export::php;
+
+    assert_eq!(php!(null), Value::Null);
+    assert_eq!(php!([]), Value::Array(PhpArray::new()));
+    assert_eq!(php!(1 + 2), Value::Int(3));
+
+    let name = "app".to_owned();
+    let value = php!([
+        "name" => name,
+        5 => "five",
+        "six",
+        "-1" => [1, [2], null],
+        "bytes" => Value::String(vec![0xff]),
+    ]);
+    let expected: Value = serde_var_export::from_str(
+        r#"array(
+  'name' => 'app',
+  5 => 'five',
+  6 => 'six',
+  -1 => array(1, array(2), NULL),
+  'bytes' => "\xff",
+)"#,
+    )
+    .unwrap();
+    assert_eq!(value, expected);
+
+    // The entries don't count towards the recursion limit one token at a time.
+    let port = 80;
+    let value = php!([
+        "k0" => 0, "k1" => 1, "k2" => 2, "k3" => 3, "k4" => 4, "k5" => 5, "k6" => 6, "k7" => 7, "k8" => 8, "k9" => 9,
+        "k10" => 10, "k11" => 11, "k12" => 12, "k13" => 13, "k14" => 14, "k15" => 15, "k16" => 16, "k17" => 17, "k18" => 18, "k19" => 19,
+        "k20" => 20, "k21" => 21, "k22" => 22, "k23" => 23, "k24" => 24, "k25" => 25, "k26" => 26, "k27" => 27, "k28" => 28, "k29" => 29,
+        "k30" => 30, "k31" => 31, "k32" => 32, "k33" => 33, "k34" => 34, "k35" => 35, "k36" => 36, "k37" => 37, "k38" => 38, "k39" => 39,
+        "k40" => 40, "k41" => 41, "k42" => 42, "k43" => 43, "k44" => 44, "k45" => 45, "k46" => 46, "k47" => 47, "k48" => 48, "k49" => 49,
+        "k50" => 50, "k51" => 51, "k52" => 52, "k53" => 53, "k54" => 54, "k55" => 55, "k56" => 56, "k57" => 57, "k58" => 58, "k59" => 59,
+        "k60" => 60, "k61" => 61, "k62" => 62, "k63" => 63, "k64" => 64, "k65" => 65, "k66" => 66, "k67" => 67, "k68" => 68, "k69" => 69,
+        "k70" => 70, "k71" => 71, "k72" => 72, "k73" => 73, "k74" => 74, "k75" => 75, "k76" => 76, "k77" => 77, "k78" => 78, "k79" => 79,
+        "k80" => 80, "k81" => 81, "k82" => 82, "k83" => 83, "k84" => 84, "k85" => 85, "k86" => 86, "k87" => 87, "k88" => 88, "k89" => 89,
+        "k90" => 90, "k91" => 91, "k92" => 92, "k93" => 93, "k94" => 94, "k95" => 95, "k96" => 96, "k97" => 97, "k98" => 98, "k99" => 99,
+        "port" => port + 1, -port, [1 => "a"], null
+    ]);
+    match value {
+        Value::Array(array) => {
+            assert_eq!(array.len(), 104);
+            assert_eq!(array.get("k99"), Some(&Value::Int(99)));
+            assert_eq!(array.get("port"), Some(&Value::Int(81)));
+            assert_eq!(array.get(0), Some(&Value::Int(-80)));
+            assert_eq!(array.get(2), Some(&Value::Null));
+        }
+        value => panic!("not an array: {:?}", value),
+    }
+}
+
+#[test]
+fn index_value() {
+    use serde_var_export::php;
+
+    let mut config = php!([
+        "database" => ["connections" => ["mysql" => ["host" => "localhost"]]],
+        "hosts" => ["a", "b"],
+        "pdo" => Value::Object {
+            class: "PDO".to_owned(),
+            properties: PhpArray::new(),
+        },
+        "debug" => false,
+    ]);
+    assert_eq!(
+        config["database"]["connections"]["mysql"]["host"],
+        string("localhost")
+    );
+    assert_eq!(config["hosts"][1], string("b"));
+    assert_eq!(config["hosts"]["1"], string("b"));
+    assert_eq!(config["missing"][0], Value::Null);
+    assert_eq!(config["debug"]["x"], Value::Null);
+
+    config["database"]["connections"]["mysql"]["port"] = php!(3306);
+    config["cache"]["driver"] = php!("redis");
+    config["pdo"]["dsn"] = php!("mysql:");
+    let key = "hosts".to_owned();
+    config[&key][5] = php!("c");
+    assert_eq!(
+        config,
+        php!([
+            "database" => ["connections" => ["mysql" => ["host" => "localhost", "port" => 3306]]],
+            "hosts" => ["a", "b", 5 => "c"],
+            "pdo" => Value::Object {
+                class: "PDO".to_owned(),
+                properties: vec![("dsn", string("mysql:"))].into_iter().collect(),
+            },
+            "debug" => false,
+            "cache" => ["driver" => "redis"],
+        ])
+    );
+}
+
+#[test]
+#[should_panic(expected = "cannot index into a scalar value Bool(false)")]
+fn index_scalar_value() {
+    let mut value = Value::Bool(false);
+    value["x"] = Value::Null;
+}
+
+#[test]
+fn value_pointer() {
+    use serde_var_export::php;
+
+    let mut config = php!([
+        "database" => ["connections" => [["host" => "a"], ["host" => "b"]]],
+        "dotted.key" => 1,
+    ]);
+    assert_eq!(
+        config.pointer("database.connections[1].host"),
+        Some(&string("b"))
+    );
+    assert_eq!(
+        config.pointer("database.connections.0.host"),
+        Some(&string("a"))
+    );
+    assert_eq!(config.pointer(""), Some(&config));
+    assert_eq!(config.pointer("database.connections[2]"), None);
+    assert_eq!(config.pointer("database.connections[0].host.x"), None);
+    assert_eq!(config.pointer("dotted.key"), None);
+
+    *config.pointer_mut("database.connections[0].host").unwrap() = string("c");
+    assert_eq!(config["database"]["connections"][0]["host"], string("c"));
+    assert_eq!(config.pointer_mut("database.port"), None);
+
+    assert_eq!(
+        config.remove_path("database.connections[0]"),
+        Some(php!(["host" => "c"]))
+    );
+    assert_eq!(config.remove_path("database.connections[0]"), None);
+    assert_eq!(config.remove_path("database.missing.key"), None);
+    assert_eq!(config.remove_path(""), None);
+    assert_eq!(
+        config,
+        php!([
+            "database" => ["connections" => [1 => ["host" => "b"]]],
+            "dotted.key" => 1,
+        ])
+    );
+}
+
+#[test]
+fn non_utf8_keys() {
+    let s = r#"array(
+  "\xFF" => 1,
+)"#;
+    let value: Value = serde_var_export::from_str(s).unwrap();
+    assert_eq!(serde_var_export::to_string(&value).unwrap(), s);
+    assert_eq!(serde_var_export::to_value(&value).unwrap(), value);
+}